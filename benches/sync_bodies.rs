@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use specs::{Builder, RunNow, World, WorldExt};
+use specs_physics::{
+    nalgebra::Isometry3,
+    nphysics::{algebra::Velocity3, object::BodyStatus},
+    physics_dispatcher,
+    systems::SyncBodiesFromPhysicsSystem,
+    PhysicsBodyBuilder,
+    SimplePosition,
+};
+
+// creates a World with the given amount of moving bodies, which have been
+// stepped once so that their RigidBodies exist in the nphysics World
+fn moving_bodies(count: usize) -> World {
+    let mut world = World::new();
+    let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+    dispatcher.setup(&mut world);
+
+    for i in 0..count {
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(i as f32, 0.0, 0.0))
+                    .build(),
+            )
+            .build();
+    }
+    dispatcher.dispatch(&world);
+
+    world
+}
+
+fn sync_bodies_from_physics(c: &mut Criterion) {
+    let world = moving_bodies(50_000);
+    let mut system = SyncBodiesFromPhysicsSystem::<f32, SimplePosition<f32>>::default();
+
+    c.bench_function("sync 50k bodies", |b| b.iter(|| system.run_now(&world)));
+}
+
+criterion_group!(benches, sync_bodies_from_physics);
+criterion_main!(benches);
@@ -0,0 +1,98 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use specs::{Builder, Dispatcher, World, WorldExt};
+use specs_physics::{
+    colliders::{IntoMesh, MeshData, MeshHandle, Shape},
+    nalgebra::{Isometry3, Point3},
+    physics_dispatcher,
+    PhysicsColliderBuilder,
+    SimplePosition,
+};
+
+/// A flat grid of 64x64 quads.
+#[derive(Clone)]
+struct Grid;
+
+impl IntoMesh for Grid {
+    type N = f32;
+
+    fn points(&self) -> MeshData<f32> {
+        const SIZE: usize = 64;
+
+        let mut points = Vec::new();
+        for x in 0..=SIZE {
+            for z in 0..=SIZE {
+                points.push(Point3::new(x as f32, 0.0, z as f32));
+            }
+        }
+
+        let mut indices = Vec::new();
+        for x in 0..SIZE {
+            for z in 0..SIZE {
+                let corner = x * (SIZE + 1) + z;
+                indices.push(Point3::new(corner, corner + 1, corner + SIZE + 2));
+                indices.push(Point3::new(corner, corner + SIZE + 2, corner + SIZE + 1));
+            }
+        }
+
+        (points, indices, None)
+    }
+}
+
+// creates a World with the given amount of not yet synchronised TriMesh
+// colliders, using the MeshHandles returned by the given closure
+fn trimesh_colliders(
+    count: usize,
+    mut mesh_handle: impl FnMut() -> MeshHandle<f32>,
+) -> (World, Dispatcher<'static, 'static>) {
+    let mut world = World::new();
+    let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+    dispatcher.setup(&mut world);
+
+    for i in 0..count {
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                i as f32 * 100.0,
+                0.0,
+                0.0,
+            )))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::TriMesh {
+                    handle: mesh_handle(),
+                })
+                .build(),
+            )
+            .build();
+    }
+
+    (world, dispatcher)
+}
+
+fn spawn_trimesh_colliders(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spawn 100 trimesh colliders");
+
+    // every collider shares the same MeshHandle, so the ShapeCache builds the
+    // TriMesh once
+    group.bench_function("with cache", |b| {
+        let mesh_handle = MeshHandle::new(Grid);
+        b.iter_batched(
+            || trimesh_colliders(100, || mesh_handle.clone()),
+            |(world, mut dispatcher)| dispatcher.dispatch(&world),
+            BatchSize::LargeInput,
+        )
+    });
+
+    // every collider has its own MeshHandle, so each TriMesh is built anew
+    group.bench_function("without cache", |b| {
+        b.iter_batched(
+            || trimesh_colliders(100, || MeshHandle::new(Grid)),
+            |(world, mut dispatcher)| dispatcher.dispatch(&world),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, spawn_trimesh_colliders);
+criterion_main!(benches);
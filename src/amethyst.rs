@@ -2,8 +2,16 @@ use amethyst_core::{SystemBundle, Transform};
 use amethyst_error::Error;
 use specs::{DispatcherBuilder, World};
 
-use crate::{nalgebra::Isometry3, register_physics_systems, Position};
+use crate::{
+    nalgebra::{Isometry3, Vector3},
+    parameters::{Gravity, PhysicsIntegrationParameters},
+    register_all_physics_systems,
+    register_physics_systems,
+    Position,
+};
 
+/// `Transform` only exposes its isometry to the synchronisation `System`s, so
+/// any scale applied to the `Transform` is left untouched.
 impl Position<f32> for Transform {
     fn isometry(&self) -> &Isometry3<f32> {
         self.isometry()
@@ -18,15 +26,152 @@ impl Position<f32> for Transform {
     }
 }
 
-pub struct PhysicsBundle;
+/// The `PhysicsBundle` registers the required physics `System`s with the
+/// `Transform` as `Position`; the optional `System`s are only registered with
+/// `PhysicsBundle::with_optional_systems`. Gravity and integration parameters
+/// can optionally be set up front; they are inserted as `Gravity` and
+/// `PhysicsIntegrationParameters` resources and may be modified at runtime.
+#[derive(Default)]
+pub struct PhysicsBundle {
+    gravity: Option<Vector3<f32>>,
+    integration_parameters: Option<PhysicsIntegrationParameters<f32>>,
+    optional_systems: bool,
+}
+
+impl PhysicsBundle {
+    /// Creates a new `PhysicsBundle` with the default gravity and integration
+    /// parameters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the `gravity` value of the `PhysicsBundle`.
+    pub fn with_gravity(mut self, gravity: Vector3<f32>) -> Self {
+        self.gravity = Some(gravity);
+        self
+    }
+
+    /// Sets the `integration_parameters` value of the `PhysicsBundle`.
+    pub fn with_integration_parameters(
+        mut self,
+        integration_parameters: PhysicsIntegrationParameters<f32>,
+    ) -> Self {
+        self.integration_parameters = Some(integration_parameters);
+        self
+    }
+
+    /// Additionally registers all optional physics `System`s, such as the
+    /// `ApplyForcesSystem` and the `TriggerEventsSystem`.
+    pub fn with_optional_systems(mut self) -> Self {
+        self.optional_systems = true;
+        self
+    }
+
+    /// Sets the maximum number of CCD substeps of the integration parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_ccd_substeps` is `0`.
+    pub fn with_max_ccd_substeps(mut self, max_ccd_substeps: usize) -> Self {
+        assert!(max_ccd_substeps > 0, "max_ccd_substeps must be at least 1");
+        self.integration_parameters
+            .get_or_insert_with(PhysicsIntegrationParameters::default)
+            .max_ccd_substeps = max_ccd_substeps;
+        self
+    }
+}
 
 impl<'a, 'b> SystemBundle<'a, 'b> for PhysicsBundle {
     fn build(
         self,
-        _world: &mut World,
+        world: &mut World,
         dispatcher: &mut DispatcherBuilder<'a, 'b>,
     ) -> Result<(), Error> {
-        register_physics_systems::<f32, Transform>(dispatcher);
+        if let Some(gravity) = self.gravity {
+            world.insert(Gravity(gravity));
+        }
+        if let Some(integration_parameters) = self.integration_parameters {
+            world.insert(integration_parameters);
+        }
+
+        if self.optional_systems {
+            register_all_physics_systems::<f32, Transform>(dispatcher);
+        } else {
+            register_physics_systems::<f32, Transform>(dispatcher);
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use amethyst_core::{SystemBundle, Transform};
+    use specs::prelude::*;
+
+    use super::PhysicsBundle;
+    use crate::{
+        nalgebra::Vector3,
+        nphysics::{algebra::Velocity3, object::BodyStatus},
+        parameters::PhysicsIntegrationParameters,
+        physics_dispatcher,
+        Physics,
+        PhysicsBodyBuilder,
+    };
+
+    #[test]
+    fn preserve_scale() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, Transform>();
+        dispatcher.setup(&mut world);
+
+        // create a moving Entity with a scaled Transform
+        let mut transform = Transform::default();
+        transform.set_scale(Vector3::new(2.0, 2.0, 2.0));
+        let entity = world
+            .create_entity()
+            .with(transform)
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(1.0, 0.0, 0.0))
+                    .build(),
+            )
+            .build();
+        for _ in 0..5 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the translation was synchronised while the scale survived
+        let transforms = world.read_storage::<Transform>();
+        let transform = transforms.get(entity).unwrap();
+        assert!(transform.translation().x > 0.0);
+        assert_eq!(*transform.scale(), Vector3::new(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn bundle_parameters() {
+        let mut world = World::new();
+        let mut dispatcher_builder = DispatcherBuilder::new();
+        PhysicsBundle::new()
+            .with_gravity(Vector3::new(0.0, -9.81, 0.0))
+            .with_integration_parameters(PhysicsIntegrationParameters {
+                error_reduction_parameter: 0.5,
+                ..PhysicsIntegrationParameters::default()
+            })
+            .build(&mut world, &mut dispatcher_builder)
+            .unwrap();
+        let mut dispatcher = dispatcher_builder.build();
+        dispatcher.setup(&mut world);
+        dispatcher.dispatch(&world);
+
+        // the bundle's parameters were applied to the nphysics World
+        let physics = world.read_resource::<Physics<f32>>();
+        assert_eq!(*physics.gravity(), Vector3::new(0.0, -9.81, 0.0));
+        assert_eq!(physics.integration_parameters().erp, 0.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn bundle_rejects_zero_ccd_substeps() {
+        PhysicsBundle::new().with_max_ccd_substeps(0);
+    }
+}
@@ -1,4 +1,6 @@
-use specs::{Component, DenseVecStorage, FlaggedStorage};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use specs::{Component, DenseVecStorage, FlaggedStorage, NullStorage};
 
 use crate::{
     nalgebra::{Isometry3, Matrix3, Point3, RealField, Vector3},
@@ -9,15 +11,54 @@ use crate::{
 };
 
 pub mod util {
+    #[cfg(feature = "serialize")]
+    use serde::{Deserialize, Serialize};
     use specs::{Component, DenseVecStorage, FlaggedStorage};
 
     use crate::{
         bodies::Position,
-        nalgebra::{Isometry3, RealField},
+        nalgebra::{Isometry3, RealField, Translation3},
     };
 
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+    #[derive(Clone, Copy, Debug, PartialEq)]
     pub struct SimplePosition<N: RealField>(pub Isometry3<N>);
 
+    impl<N: RealField> SimplePosition<N> {
+        /// Creates a new `SimplePosition` from the given `Isometry3`.
+        pub fn from_isometry(isometry: Isometry3<N>) -> Self {
+            Self(isometry)
+        }
+
+        /// Creates a new, unrotated `SimplePosition` at the given translation.
+        pub fn from_translation(x: N, y: N, z: N) -> Self {
+            Self(Isometry3::translation(x, y, z))
+        }
+
+        /// Interpolates between this and the `other` `SimplePosition`; the
+        /// translation is interpolated linearly, the rotation spherically. `t`
+        /// is expected to be within `[0, 1]`.
+        pub fn lerp(&self, other: &Self, t: N) -> Self {
+            let translation = self
+                .0
+                .translation
+                .vector
+                .lerp(&other.0.translation.vector, t);
+            // slerp is not well-defined for rotations that are 180 degrees apart,
+            // fall back to nlerp in that case
+            let rotation = self
+                .0
+                .rotation
+                .try_slerp(&other.0.rotation, t, N::default_epsilon())
+                .unwrap_or_else(|| self.0.rotation.nlerp(&other.0.rotation, t));
+
+            Self(Isometry3::from_parts(
+                Translation3::from(translation),
+                rotation,
+            ))
+        }
+    }
+
     impl<N: RealField> Position<N> for SimplePosition<N> {
         fn isometry(&self) -> &Isometry3<N> {
             &self.0
@@ -37,6 +78,24 @@ pub mod util {
     impl<N: RealField> Component for SimplePosition<N> {
         type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
     }
+
+    /// The `NetworkSmoothing` `Component` eases the `SimplePosition` of its
+    /// `Entity` towards an authoritative `target`, e.g. the latest pose
+    /// received from a server, via the `NetworkSmoothingSystem`. The remaining
+    /// distance decays exponentially with `rate` per second, independent of
+    /// the frame rate; after `1 / rate` seconds about 63% of a correction is
+    /// applied. Corrections larger than `snap_distance` are applied
+    /// immediately.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct NetworkSmoothing<N: RealField> {
+        pub target: SimplePosition<N>,
+        pub rate: N,
+        pub snap_distance: N,
+    }
+
+    impl<N: RealField> Component for NetworkSmoothing<N> {
+        type Storage = DenseVecStorage<Self>;
+    }
 }
 
 /// An implementation of the `Position` trait is required for the
@@ -53,7 +112,6 @@ pub trait Position<N: RealField>:
     fn set_isometry(&mut self, isometry: &Isometry3<N>) -> &mut Self;
 }
 
-
 /// The `PhysicsBody` `Component` represents a `PhysicsWorld` `RigidBody` in
 /// Specs and contains all the data required for the synchronisation between
 /// both worlds.
@@ -62,11 +120,19 @@ pub struct PhysicsBody<N: RealField> {
     pub(crate) handle: Option<DefaultBodyHandle>,
     pub gravity_enabled: bool,
     pub body_status: BodyStatus,
+    /// The linear and angular velocity of the `RigidBody`; updated after every
+    /// simulation step.
     pub velocity: Velocity3<N>,
     pub angular_inertia: Matrix3<N>,
     pub mass: N,
     pub local_center_of_mass: Point3<N>,
+    /// Locks the rotation around the respective axes; locked axes are no
+    /// longer affected by forces, impulses or contacts and retain their
+    /// current angular velocity.
     pub rotations_kinematic: Vector3<bool>,
+    /// Locks the translation along the respective axes, see
+    /// `rotations_kinematic`.
+    pub translations_kinematic: Vector3<bool>,
     external_forces: Force3<N>,
 }
 
@@ -93,6 +159,8 @@ impl<N: RealField> PhysicsBody<N> {
             .angular_inertia(self.angular_inertia)
             .mass(self.mass)
             .local_center_of_mass(self.local_center_of_mass)
+            .kinematic_rotations(self.rotations_kinematic)
+            .kinematic_translations(self.translations_kinematic)
     }
 
     /// Note: applies forces by draining external force property
@@ -105,6 +173,7 @@ impl<N: RealField> PhysicsBody<N> {
         rigid_body.set_local_center_of_mass(self.local_center_of_mass);
         rigid_body.apply_force(0, &self.drain_external_force(), ForceType::Force, true);
         rigid_body.set_rotations_kinematic(self.rotations_kinematic);
+        rigid_body.set_translations_kinematic(self.translations_kinematic);
         self
     }
 
@@ -126,6 +195,88 @@ impl<N: RealField> PhysicsBody<N> {
         self.external_forces = Force3::<N>::zero();
         value
     }
+
+    /// Returns a `PhysicsBodyData` describing this body.
+    pub fn data(&self) -> PhysicsBodyData<N> {
+        PhysicsBodyData {
+            gravity_enabled: self.gravity_enabled,
+            body_status: self.body_status,
+            linear_velocity: self.velocity.linear,
+            angular_velocity: self.velocity.angular,
+            angular_inertia: self.angular_inertia,
+            mass: self.mass,
+            local_center_of_mass: self.local_center_of_mass,
+            rotations_kinematic: self.rotations_kinematic,
+            translations_kinematic: self.translations_kinematic,
+        }
+    }
+}
+
+/// `PhysicsBodyData` is a plain data description of a `PhysicsBody`, which
+/// can be (de)serialized with the `serialize` feature, e.g. for save games.
+/// External forces that were not applied yet are not part of it.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsBodyData<N: RealField> {
+    pub gravity_enabled: bool,
+    #[cfg_attr(feature = "serialize", serde(with = "body_status"))]
+    pub body_status: BodyStatus,
+    pub linear_velocity: Vector3<N>,
+    pub angular_velocity: Vector3<N>,
+    pub angular_inertia: Matrix3<N>,
+    pub mass: N,
+    pub local_center_of_mass: Point3<N>,
+    pub rotations_kinematic: Vector3<bool>,
+    pub translations_kinematic: Vector3<bool>,
+}
+
+impl<N: RealField> PhysicsBodyData<N> {
+    /// Builds a `PhysicsBody` from this description.
+    pub fn build(&self) -> PhysicsBody<N> {
+        PhysicsBodyBuilder::from(self.body_status)
+            .gravity_enabled(self.gravity_enabled)
+            .velocity(Velocity3::new(self.linear_velocity, self.angular_velocity))
+            .angular_inertia(self.angular_inertia)
+            .mass(self.mass)
+            .local_center_of_mass(self.local_center_of_mass)
+            .rotations_kinematic(self.rotations_kinematic)
+            .translations_kinematic(self.translations_kinematic)
+            .build()
+    }
+}
+
+/// (De)serializes the nphysics `BodyStatus` by the name of its variant.
+#[cfg(feature = "serialize")]
+mod body_status {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::nphysics::object::BodyStatus;
+
+    const VARIANTS: &[&str] = &["Disabled", "Static", "Dynamic", "Kinematic"];
+
+    pub fn serialize<S: Serializer>(
+        body_status: &BodyStatus,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let variant = match body_status {
+            BodyStatus::Disabled => VARIANTS[0],
+            BodyStatus::Static => VARIANTS[1],
+            BodyStatus::Dynamic => VARIANTS[2],
+            BodyStatus::Kinematic => VARIANTS[3],
+        };
+        variant.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BodyStatus, D::Error> {
+        let variant = String::deserialize(deserializer)?;
+        match variant.as_str() {
+            "Disabled" => Ok(BodyStatus::Disabled),
+            "Static" => Ok(BodyStatus::Static),
+            "Dynamic" => Ok(BodyStatus::Dynamic),
+            "Kinematic" => Ok(BodyStatus::Kinematic),
+            _ => Err(D::Error::unknown_variant(&variant, VARIANTS)),
+        }
+    }
 }
 
 /// The `PhysicsBodyBuilder` implements the builder pattern for `PhysicsBody`s
@@ -157,6 +308,7 @@ pub struct PhysicsBodyBuilder<N: RealField> {
     mass: N,
     local_center_of_mass: Point3<N>,
     rotations_kinematic: Vector3<bool>,
+    translations_kinematic: Vector3<bool>,
 }
 
 impl<N: RealField> From<BodyStatus> for PhysicsBodyBuilder<N> {
@@ -171,6 +323,7 @@ impl<N: RealField> From<BodyStatus> for PhysicsBodyBuilder<N> {
             mass: N::from_f32(1.2).unwrap(),
             local_center_of_mass: Point3::origin(),
             rotations_kinematic: Vector3::new(false, false, false),
+            translations_kinematic: Vector3::new(false, false, false),
         }
     }
 }
@@ -216,6 +369,19 @@ impl<N: RealField> PhysicsBodyBuilder<N> {
         self
     }
 
+    /// Sets the `translations_kinematic` value of the `PhysicsBodyBuilder`.
+    pub fn translations_kinematic(mut self, translations_kinematic: Vector3<bool>) -> Self {
+        self.translations_kinematic = translations_kinematic;
+        self
+    }
+
+    /// Locks or unlocks the translation along all axes.
+    pub fn lock_translations(mut self, lock_translations: bool) -> Self {
+        self.translations_kinematic =
+            Vector3::new(lock_translations, lock_translations, lock_translations);
+        self
+    }
+
     /// Builds the `PhysicsBody` from the values set in the `PhysicsBodyBuilder`
     /// instance.
     pub fn build(self) -> PhysicsBody<N> {
@@ -229,6 +395,132 @@ impl<N: RealField> PhysicsBodyBuilder<N> {
             local_center_of_mass: self.local_center_of_mass,
             external_forces: Force3::zero(),
             rotations_kinematic: self.rotations_kinematic,
+            translations_kinematic: self.translations_kinematic,
         }
     }
 }
+
+/// Marks an `Entity` whose `Position` should not be synchronised from the
+/// physics world. Its `RigidBody` keeps simulating, but
+/// `SyncBodiesFromPhysicsSystem` leaves both its `Position` and `PhysicsBody`
+/// untouched, e.g. because another `System` owns the `Position`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PositionSyncDisabled;
+
+impl Component for PositionSyncDisabled {
+    type Storage = NullStorage<Self>;
+}
+
+/// The `MaxVelocity` `Component` caps the linear and angular speed of the
+/// `RigidBody` of its `Entity`. Velocities exceeding the cap after a
+/// simulation step are scaled down, preserving their direction.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MaxVelocity<N: RealField> {
+    pub linear: N,
+    pub angular: N,
+}
+
+impl<N: RealField> Component for MaxVelocity<N> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The `PreviousPosition` `Component` holds the isometry the `RigidBody` of
+/// its `Entity` had before the last simulation step, e.g. to interpolate
+/// rendered positions between steps or to compute the per-step displacement.
+/// It is updated by the `PhysicsStepperSystem` right before each step for all
+/// `Entity`s with a `PhysicsBody`; `Entity`s without one are left untouched.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PreviousPosition<N: RealField>(pub Isometry3<N>);
+
+impl<N: RealField> Default for PreviousPosition<N> {
+    fn default() -> Self {
+        Self(Isometry3::identity())
+    }
+}
+
+impl<N: RealField> Component for PreviousPosition<N> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        nalgebra::{Isometry3, Matrix3, UnitQuaternion, Vector3},
+        nphysics::{algebra::Velocity3, object::BodyStatus},
+        PhysicsBodyBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn construct_simple_position() {
+        assert_eq!(
+            SimplePosition::<f32>::from_translation(1.0, 2.0, 3.0).0,
+            Isometry3::translation(1.0, 2.0, 3.0)
+        );
+        assert_eq!(
+            SimplePosition::<f32>::from_isometry(Isometry3::rotation(Vector3::y())).0,
+            Isometry3::rotation(Vector3::y())
+        );
+    }
+
+    #[test]
+    fn lerp_simple_position() {
+        let from = SimplePosition::<f32>::from_translation(0.0, 0.0, 0.0);
+        let to = SimplePosition::<f32>::from_isometry(Isometry3::new(
+            Vector3::new(2.0, 4.0, 6.0),
+            Vector3::y() * std::f32::consts::FRAC_PI_2,
+        ));
+
+        // the midpoint is halfway in both translation and rotation
+        let midpoint = from.lerp(&to, 0.5);
+        assert_eq!(midpoint.0.translation.vector, Vector3::new(1.0, 2.0, 3.0));
+        assert!(
+            midpoint
+                .0
+                .rotation
+                .angle_to(&UnitQuaternion::from_scaled_axis(
+                    Vector3::y() * std::f32::consts::FRAC_PI_4
+                ))
+                .abs()
+                < 1.0e-3
+        );
+    }
+
+    #[test]
+    fn describe_physics_body() {
+        let physics_body = PhysicsBodyBuilder::<f32>::from(BodyStatus::Kinematic)
+            .gravity_enabled(true)
+            .velocity(Velocity3::new(Vector3::x(), Vector3::y()))
+            .angular_inertia(Matrix3::from_diagonal_element(2.0))
+            .lock_rotations(true)
+            .build();
+
+        let data = physics_body.data();
+        assert_eq!(data.body_status, BodyStatus::Kinematic);
+        assert_eq!(data.linear_velocity, Vector3::x());
+        assert_eq!(data.angular_velocity, Vector3::y());
+        assert_eq!(data.build().data(), data);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn serialize_physics_body_data() {
+        let data = PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+            .mass(2.0)
+            .build()
+            .data();
+        let serialized = serde_json::to_string(&data).unwrap();
+        assert!(serialized.contains("\"body_status\":\"Dynamic\""));
+        let deserialized: super::PhysicsBodyData<f32> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, data);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn serialize_simple_position() {
+        let position = SimplePosition::<f32>(Isometry3::translation(1.0, 2.0, 3.0));
+        let serialized = serde_json::to_string(&position).unwrap();
+        let deserialized: SimplePosition<f32> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, position);
+    }
+}
@@ -0,0 +1,343 @@
+use specs::{Builder, Entity};
+
+use crate::{
+    colliders::Shape,
+    joints::{BallMarker, FixedMarker, JointType, PhysicsJoint, PrismaticMarker, RevoluteMarker},
+    nalgebra::RealField,
+    nphysics::object::BodyStatus,
+    PhysicsBodyBuilder,
+    PhysicsCollider,
+    PhysicsColliderBuilder,
+    PhysicsParent,
+};
+
+/// Extension trait adding physics related convenience methods to Specs
+/// `Builder`s, such as the `EntityBuilder`.
+pub trait EntityBuilderExt: Builder + Sized {
+    /// Adds the given `PhysicsJoint` to the `Entity` and marks it with the
+    /// marker `Component` of its `JointType`, so the `Entity` immediately
+    /// shows up in `ReadJoints` and `WriteJoints` joins.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use specs::{Builder, World, WorldExt};
+    /// use specs_physics::{
+    ///     joints::{BallJoint, JointType},
+    ///     nalgebra::{Isometry3, Point3},
+    ///     nphysics::object::BodyStatus,
+    ///     EntityBuilderExt,
+    ///     PhysicsBodyBuilder,
+    ///     PhysicsJointBuilder,
+    ///     SimplePosition,
+    /// };
+    ///
+    /// let mut world = World::new();
+    /// let mut dispatcher = specs_physics::physics_dispatcher::<f32, SimplePosition<f32>>();
+    /// dispatcher.setup(&mut world);
+    ///
+    /// world
+    ///     .create_entity()
+    ///     .with(SimplePosition::<f32>(Isometry3::identity()))
+    ///     .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+    ///     .with_joint(
+    ///         PhysicsJointBuilder::from(JointType::Ball(BallJoint {
+    ///             local_anchor_1: Point3::origin(),
+    ///             local_anchor_2: Point3::new(0.0, 1.0, 0.0),
+    ///         }))
+    ///         .build(),
+    ///     )
+    ///     .build();
+    /// ```
+    fn with_joint<N: RealField>(self, joint: PhysicsJoint<N>) -> Self {
+        let builder = match joint.joint_type {
+            JointType::Revolute(_) => self.with(RevoluteMarker),
+            JointType::Prismatic(_) => self.with(PrismaticMarker),
+            JointType::Ball(_) => self.with(BallMarker),
+            JointType::Fixed(_) => self.with(FixedMarker),
+        };
+
+        builder.with(joint)
+    }
+
+    /// Adds a static `PhysicsBody` to the `Entity`, turning it into immovable
+    /// ground geometry.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use specs::{Builder, World, WorldExt};
+    /// use specs_physics::{
+    ///     nalgebra::Isometry3,
+    ///     nphysics::object::BodyStatus,
+    ///     EntityBuilderExt,
+    ///     PhysicsBody,
+    ///     SimplePosition,
+    /// };
+    ///
+    /// let mut world = World::new();
+    /// let mut dispatcher = specs_physics::physics_dispatcher::<f32, SimplePosition<f32>>();
+    /// dispatcher.setup(&mut world);
+    ///
+    /// let ground = world
+    ///     .create_entity()
+    ///     .with(SimplePosition::<f32>(Isometry3::identity()))
+    ///     .with_ground::<f32>()
+    ///     .build();
+    /// let body = world
+    ///     .create_entity()
+    ///     .with(SimplePosition::<f32>(Isometry3::translation(0.0, 2.0, 0.0)))
+    ///     .with_rigid_body::<f32>(BodyStatus::Dynamic)
+    ///     .build();
+    ///
+    /// let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+    /// assert_eq!(physics_bodies.get(ground).unwrap().body_status, BodyStatus::Static);
+    /// assert_eq!(physics_bodies.get(body).unwrap().body_status, BodyStatus::Dynamic);
+    /// ```
+    fn with_ground<N: RealField>(self) -> Self {
+        self.with_rigid_body::<N>(BodyStatus::Static)
+    }
+
+    /// Adds a `PhysicsBody` with the given `BodyStatus` and the defaults of
+    /// the `PhysicsBodyBuilder` to the `Entity`.
+    fn with_rigid_body<N: RealField>(self, body_status: BodyStatus) -> Self {
+        self.with(PhysicsBodyBuilder::<N>::from(body_status).build())
+    }
+
+    /// Attaches the given `PhysicsCollider` to the `PhysicsBody` of
+    /// `body_entity` by adding it together with a `PhysicsParent` to the
+    /// `Entity`. The `offset_from_parent` of the `PhysicsCollider` is relative
+    /// to the `PhysicsBody`.
+    ///
+    /// Colliders can be attached to and detached from a live `PhysicsBody` at
+    /// any time, e.g. via `LazyUpdate`; the `PhysicsBody` keeps its velocity
+    /// and sleep state while nphysics updates its mass and center of mass with
+    /// the density of the added or removed `PhysicsCollider`. To detach the
+    /// `PhysicsCollider` again, remove it from the `Entity` or delete the
+    /// `Entity`, see `colliders::detach_collider`.
+    ///
+    /// As all `PhysicsCollider`s, the `Entity` requires a `Position` to be
+    /// synchronised into the physics world.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use specs::{Builder, World, WorldExt};
+    /// use specs_physics::{
+    ///     colliders::Shape,
+    ///     nalgebra::{Isometry3, Vector3},
+    ///     nphysics::object::BodyStatus,
+    ///     EntityBuilderExt,
+    ///     PhysicsColliderBuilder,
+    ///     SimplePosition,
+    /// };
+    ///
+    /// let mut world = World::new();
+    /// let mut dispatcher = specs_physics::physics_dispatcher::<f32, SimplePosition<f32>>();
+    /// dispatcher.setup(&mut world);
+    ///
+    /// let body = world
+    ///     .create_entity()
+    ///     .with(SimplePosition::<f32>(Isometry3::identity()))
+    ///     .with_rigid_body::<f32>(BodyStatus::Dynamic)
+    ///     .build();
+    /// let shield = world
+    ///     .create_entity()
+    ///     .with(SimplePosition::<f32>(Isometry3::identity()))
+    ///     .attach_collider(
+    ///         body,
+    ///         PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+    ///             half_extents: Vector3::new(0.1, 1.0, 1.0),
+    ///         })
+    ///         .offset_from_parent(Isometry3::translation(1.0, 0.0, 0.0))
+    ///         .build(),
+    ///     )
+    ///     .build();
+    /// ```
+    fn attach_collider<N: RealField>(
+        self,
+        body_entity: Entity,
+        collider: PhysicsCollider<N>,
+    ) -> Self {
+        self.with(PhysicsParent {
+            entity: body_entity,
+        })
+        .with(collider)
+    }
+
+    /// Adds a sensor `PhysicsCollider` with the given `Shape` and the defaults
+    /// of the `PhysicsColliderBuilder` to the `Entity`, e.g. for triggers and
+    /// detection zones. The sensor is attached to the `PhysicsBody` of the
+    /// `Entity`, if any, and to the ground otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use specs::{Builder, World, WorldExt};
+    /// use specs_physics::{
+    ///     colliders::Shape,
+    ///     nalgebra::Isometry3,
+    ///     EntityBuilderExt,
+    ///     SimplePosition,
+    /// };
+    ///
+    /// let mut world = World::new();
+    /// let mut dispatcher = specs_physics::physics_dispatcher::<f32, SimplePosition<f32>>();
+    /// dispatcher.setup(&mut world);
+    ///
+    /// let detection_zone = world
+    ///     .create_entity()
+    ///     .with(SimplePosition::<f32>(Isometry3::identity()))
+    ///     .with_sensor::<f32>(Shape::Ball { radius: 5.0 })
+    ///     .build();
+    /// ```
+    fn with_sensor<N: RealField>(self, shape: Shape<N>) -> Self {
+        self.with(PhysicsColliderBuilder::from(shape).sensor(true).build())
+    }
+}
+
+impl<B: Builder> EntityBuilderExt for B {}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        colliders::{detach_collider, Shape},
+        joints::{JointType, RevoluteJoint, RevoluteMarker},
+        nalgebra::{Isometry3, Point3, Vector3},
+        nphysics::object::BodyStatus,
+        physics_dispatcher,
+        EntityBuilderExt,
+        Physics,
+        PhysicsBodyBuilder,
+        PhysicsCollider,
+        PhysicsColliderBuilder,
+        PhysicsJointBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn attach_collider() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a static ground collider and a body hovering above it
+        let ground = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 1.0, 10.0),
+                })
+                .build(),
+            )
+            .build();
+        let body = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 4.0, 0.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        let rigid_body_mass = |world: &World| {
+            let physics = world.read_resource::<Physics<f32>>();
+            let handle = physics.body_handles.get(&body.id()).cloned().unwrap();
+            physics.bodies.rigid_body(handle).unwrap().mass()
+        };
+        let mass = rigid_body_mass(&world);
+
+        // attach a second collider to the live body that reaches into the ground
+        let leg = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .attach_collider(
+                body,
+                PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 })
+                    .offset_from_parent(Isometry3::translation(0.0, -2.8, 0.0))
+                    .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+
+        // the contacts of the ground now include the new shape and the body gained
+        // its mass
+        {
+            let physics = world.read_resource::<Physics<f32>>();
+            let entities = world.entities();
+            let contacts = physics
+                .contacts_with(&entities, ground, false)
+                .collect::<Vec<_>>();
+            assert_eq!(contacts, vec![leg]);
+        }
+        assert!(rigid_body_mass(&world) > mass);
+
+        // detaching the collider restores the original mass
+        detach_collider(&mut world.write_storage::<PhysicsCollider<f32>>(), leg);
+        dispatcher.dispatch(&world);
+        assert!((rigid_body_mass(&world) - mass).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn with_joint() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity carrying a RevoluteJoint anchored to the ground
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::translation(
+                0.0, 1.0, 0.0,
+            )))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with_joint(
+                PhysicsJointBuilder::<f32>::from(JointType::Revolute(RevoluteJoint {
+                    local_anchor_1: Point3::origin(),
+                    local_axis_1: Vector3::z_axis(),
+                    local_anchor_2: Point3::new(0.0, 1.0, 0.0),
+                    local_axis_2: Vector3::z_axis(),
+                }))
+                .build(),
+            )
+            .build();
+
+        // the marker is attached right away
+        assert!(world.read_storage::<RevoluteMarker>().get(entity).is_some());
+
+        dispatcher.dispatch(&world);
+
+        // fetch the Physics instance and check for the new joint
+        let physics = world.read_resource::<Physics<f32>>();
+        let handle = physics.joint_handles.get(&entity.id()).unwrap();
+        assert!(physics.joint_constraints.get(*handle).is_some());
+    }
+
+    #[test]
+    fn with_sensor() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a body carrying a sensor zone
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with_sensor::<f32>(Shape::Ball { radius: 5.0 })
+            .build();
+        dispatcher.dispatch(&world);
+
+        // the nphysics Collider is a sensor attached to the body
+        let physics = world.read_resource::<Physics<f32>>();
+        let handle = physics.collider_handles.get(&entity.id()).unwrap();
+        let collider = physics.colliders.get(*handle).unwrap();
+        assert!(collider.is_sensor());
+        assert_eq!(
+            collider.body(),
+            *physics.body_handles.get(&entity.id()).unwrap()
+        );
+    }
+}
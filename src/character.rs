@@ -0,0 +1,102 @@
+//! # Character module
+//! Kinematic character controllers.
+//!
+//! A character is an `Entity` with a kinematic `PhysicsBody` and a
+//! `CharacterController` `Component`. Instead of being pushed around by the
+//! solver, the `CharacterControllerSystem` sweeps an upright capsule along
+//! the desired `movement` before every simulation step. The capsule slides
+//! along walls, climbs slopes up to `max_slope` and steps over obstacles up to
+//! `step_height`; the resulting motion is handed to the kinematic
+//! `RigidBody` as its velocity, so the character still pushes dynamic bodies
+//! out of its way.
+
+use specs::{Component, DenseVecStorage};
+
+use crate::{
+    nalgebra::{RealField, Vector3},
+    GroundInfo,
+};
+
+/// The `CharacterController` `Component` moves the kinematic `RigidBody` of
+/// its `Entity` like a capsule with the given `half_height` and `radius`,
+/// centered on the position of the body and aligned with the y axis. Up is
+/// the positive y axis.
+///
+/// # Example
+///
+/// ```rust
+/// use specs_physics::{character::CharacterController, nalgebra::Vector3};
+///
+/// let character = CharacterController::<f32>::new(0.5, 0.4)
+///     .with_step_height(0.3)
+///     .with_max_slope(std::f32::consts::FRAC_PI_4)
+///     .with_movement(Vector3::new(2.0, 0.0, 0.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CharacterController<N: RealField> {
+    /// Half the height of the cylindrical part of the capsule.
+    pub half_height: N,
+    /// The radius of the capsule.
+    pub radius: N,
+    /// The height of the obstacles the character steps onto instead of being
+    /// blocked by them; also the distance the character sticks to the ground
+    /// when walking down slopes or stairs.
+    pub step_height: N,
+    /// The steepest slope the character can walk up, in radians; steeper
+    /// surfaces block the character like walls.
+    pub max_slope: N,
+    /// The desired horizontal velocity of the character, in units per
+    /// second; the y component is ignored.
+    pub movement: Vector3<N>,
+    /// The vertical velocity of the character, accelerated by gravity while
+    /// it is airborne. Set it to a positive value to jump.
+    pub vertical_velocity: N,
+    /// The ground the character stands on after the last step, if any;
+    /// updated by the `CharacterControllerSystem`.
+    pub ground: Option<GroundInfo<N>>,
+}
+
+impl<N: RealField> CharacterController<N> {
+    /// Creates a new `CharacterController` with a capsule of the given
+    /// dimensions that neither moves nor steps onto obstacles and walks up
+    /// slopes of up to 45 degrees.
+    pub fn new(half_height: N, radius: N) -> Self {
+        Self {
+            half_height,
+            radius,
+            step_height: N::zero(),
+            max_slope: N::frac_pi_4(),
+            movement: Vector3::zeros(),
+            vertical_velocity: N::zero(),
+            ground: None,
+        }
+    }
+
+    /// Sets the `step_height` value of the `CharacterController`.
+    pub fn with_step_height(mut self, step_height: N) -> Self {
+        self.step_height = step_height;
+        self
+    }
+
+    /// Sets the `max_slope` value of the `CharacterController`.
+    pub fn with_max_slope(mut self, max_slope: N) -> Self {
+        self.max_slope = max_slope;
+        self
+    }
+
+    /// Sets the `movement` value of the `CharacterController`.
+    pub fn with_movement(mut self, movement: Vector3<N>) -> Self {
+        self.movement = movement;
+        self
+    }
+
+    /// Returns `true` if the character stood on walkable ground after the last
+    /// step.
+    pub fn is_grounded(&self) -> bool {
+        self.ground.is_some()
+    }
+}
+
+impl<N: RealField> Component for CharacterController<N> {
+    type Storage = DenseVecStorage<Self>;
+}
@@ -1,9 +1,11 @@
-use std::{f32::consts::PI, fmt, ops::Deref};
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, f32::consts::PI, fmt, ops::Deref, sync::Arc};
 
-use specs::{Component, DenseVecStorage, FlaggedStorage};
+use specs::{Component, DenseVecStorage, Entity, FlaggedStorage, WriteStorage};
 
 use crate::{
-    nalgebra::{DMatrix, Isometry3, Point2, Point3, RealField, Unit, Vector3},
+    nalgebra::{DMatrix, Isometry3, Point2, Point3, RealField, Unit, UnitQuaternion, Vector3},
     ncollide::{
         pipeline::CollisionGroups,
         shape::{
@@ -49,10 +51,84 @@ impl<'clone, N: RealField> Clone for Box<dyn IntoMesh<N = N> + 'clone> {
     }
 }
 
+/// `MeshHandle` is a shared reference to an `IntoMesh` implementation used by
+/// `Shape::TriMesh`. Cloning a `MeshHandle` is cheap and `MeshHandle`s are
+/// compared by identity, so clones of the same `MeshHandle` are equal while
+/// two separately created `MeshHandle`s are not, even if they generate the
+/// same `MeshData`. Reuse a `MeshHandle` for all `Shape`s of the same mesh to
+/// share its `ShapeHandle` through the `ShapeCache`.
+pub struct MeshHandle<N: RealField>(Arc<dyn IntoMesh<N = N>>);
+
+impl<N: RealField> MeshHandle<N> {
+    /// Creates a new `MeshHandle` for the given mesh.
+    pub fn new<M: IntoMesh<N = N> + 'static>(mesh: M) -> Self {
+        Self(Arc::new(mesh))
+    }
+
+    /// Returns the identity of the referenced mesh.
+    fn id(&self) -> usize {
+        Arc::as_ptr(&self.0) as *const () as usize
+    }
+}
+
+impl<N: RealField> IntoMesh for MeshHandle<N> {
+    type N = N;
+
+    fn points(&self) -> MeshData<Self::N> {
+        self.0.points()
+    }
+}
+
+impl<N: RealField> Clone for MeshHandle<N> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<N: RealField> fmt::Debug for MeshHandle<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // the mesh data may be large and expensive to generate, so it is omitted
+        write!(f, "MeshHandle {{ .. }}")
+    }
+}
+
+impl<N: RealField> PartialEq for MeshHandle<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+/// `ScaledMesh` wraps an `IntoMesh` implementation and scales its points
+/// component-wise; it is used to scale `Shape::TriMesh`es.
+#[derive(Clone)]
+struct ScaledMesh<N: RealField> {
+    mesh: MeshHandle<N>,
+    scale: Vector3<N>,
+}
+
+impl<N: RealField> IntoMesh for ScaledMesh<N> {
+    type N = N;
+
+    fn points(&self) -> MeshData<Self::N> {
+        let (points, indices, uvs) = self.mesh.points();
+        let points = points
+            .into_iter()
+            .map(|point| Point3::from(point.coords.component_mul(&self.scale)))
+            .collect();
+
+        (points, indices, uvs)
+    }
+}
+
 /// `Shape` serves as an abstraction over nphysics `ShapeHandle`s and makes it
 /// easier to configure and define said `ShapeHandle`s for the user without
 /// having to know the underlying nphysics API.
-#[derive(Clone)]
+///
+/// With the `serialize` feature, `Shape`s can be (de)serialized with `serde`,
+/// except for `Shape::TriMesh`es: their mesh is a trait object, so
+/// serializing one fails.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Shape<N: RealField> {
     Ball {
         radius: N,
@@ -85,8 +161,9 @@ pub enum Shape<N: RealField> {
         a: Point3<N>,
         b: Point3<N>,
     },
+    #[cfg_attr(feature = "serialize", serde(skip))]
     TriMesh {
-        handle: Box<dyn IntoMesh<N = N>>,
+        handle: MeshHandle<N>,
     },
     Triangle {
         a: Point3<N>,
@@ -96,6 +173,108 @@ pub enum Shape<N: RealField> {
 }
 
 impl<N: RealField> Shape<N> {
+    /// Returns a copy of this `Shape` uniformly scaled by the given `factor`.
+    pub fn scaled(&self, factor: N) -> Shape<N> {
+        self.scaled_non_uniform(&Vector3::repeat(factor))
+            .unwrap_or_else(|| match self {
+                Shape::Ball { radius } => Shape::Ball {
+                    radius: *radius * factor,
+                },
+                Shape::Capsule {
+                    half_height,
+                    radius,
+                } => Shape::Capsule {
+                    half_height: *half_height * factor,
+                    radius: *radius * factor,
+                },
+                Shape::Compound { parts } => Shape::Compound {
+                    parts: parts
+                        .iter()
+                        .map(|(isometry, shape)| {
+                            let mut isometry = *isometry;
+                            isometry.translation.vector *= factor;
+                            (isometry, shape.scaled(factor))
+                        })
+                        .collect(),
+                },
+                // a uniform scale never changes the normal of a plane
+                Shape::Plane { normal } => Shape::Plane { normal: *normal },
+                _ => unreachable!("Shape supports non-uniform scaling"),
+            })
+    }
+
+    /// Returns a copy of this `Shape` scaled component-wise by the given
+    /// `scale`. Returns `None` for shapes that can't be scaled non-uniformly
+    /// without changing their type, i.e. `Ball`s and `Capsule`s as well as
+    /// `Compound`s with such or rotated parts; use `Shape::scaled` for those.
+    /// `Plane`s return `None` for a `scale` with a zero component, as their
+    /// normal would be undefined.
+    pub fn scaled_non_uniform(&self, scale: &Vector3<N>) -> Option<Shape<N>> {
+        let scale_point = |point: &Point3<N>| Point3::from(point.coords.component_mul(scale));
+
+        let shape = match self {
+            Shape::Ball { .. } | Shape::Capsule { .. } => return None,
+            Shape::Compound { parts } => Shape::Compound {
+                parts: parts
+                    .iter()
+                    .map(|(isometry, shape)| {
+                        if isometry.rotation != UnitQuaternion::identity() {
+                            return None;
+                        }
+
+                        let mut isometry = *isometry;
+                        isometry.translation.vector.component_mul_assign(scale);
+                        Some((isometry, shape.scaled_non_uniform(scale)?))
+                    })
+                    .collect::<Option<_>>()?,
+            },
+            Shape::ConvexHull { points } => Shape::ConvexHull {
+                points: points.iter().map(scale_point).collect(),
+            },
+            Shape::Cuboid { half_extents } => Shape::Cuboid {
+                half_extents: half_extents.component_mul(scale),
+            },
+            Shape::HeightField {
+                heights,
+                scale: height_field_scale,
+            } => Shape::HeightField {
+                heights: heights.clone(),
+                scale: height_field_scale.component_mul(scale),
+            },
+            // planes are infinite, only their normal is affected by the scale
+            Shape::Plane { normal } => {
+                if scale.iter().any(|component| *component == N::zero()) {
+                    return None;
+                }
+
+                Shape::Plane {
+                    normal: Unit::new_normalize(normal.component_div(scale)),
+                }
+            }
+            Shape::Polyline { points, indices } => Shape::Polyline {
+                points: points.iter().map(scale_point).collect(),
+                indices: indices.clone(),
+            },
+            Shape::Segment { a, b } => Shape::Segment {
+                a: scale_point(a),
+                b: scale_point(b),
+            },
+            Shape::TriMesh { handle } => Shape::TriMesh {
+                handle: MeshHandle::new(ScaledMesh {
+                    mesh: handle.clone(),
+                    scale: *scale,
+                }),
+            },
+            Shape::Triangle { a, b, c } => Shape::Triangle {
+                a: scale_point(a),
+                b: scale_point(b),
+                c: scale_point(c),
+            },
+        };
+
+        Some(shape)
+    }
+
     /// Converts a `Shape` and its values into its corresponding `ShapeHandle`
     /// type. The `ShapeHandle` is used to define a `Collider` in the
     /// `PhysicsWorld`.
@@ -131,16 +310,73 @@ impl<N: RealField> Shape<N> {
     }
 }
 
+/// `DebugShape` is a lightweight description of the primitive dimensions of a
+/// `Collider`s shape, e.g. for drawing wireframes in a debug renderer. Shapes
+/// without a primitive description are reported as `DebugShape::Other`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugShape<N: RealField> {
+    Ball { radius: N },
+    Capsule { half_height: N, radius: N },
+    Cuboid { half_extents: Vector3<N> },
+    Plane { normal: Unit<Vector3<N>> },
+    Other,
+}
+
+impl<N: RealField> DebugShape<N> {
+    /// Describes the given `ShapeHandle` at the given position, expanding
+    /// `Compound`s into their parts with their composed positions.
+    pub(crate) fn describe(
+        position: &Isometry3<N>,
+        shape_handle: &ShapeHandle<N>,
+        debug_shapes: &mut Vec<(Isometry3<N>, DebugShape<N>)>,
+    ) {
+        let debug_shape = if let Some(ball) = shape_handle.as_shape::<Ball<N>>() {
+            DebugShape::Ball {
+                radius: ball.radius(),
+            }
+        } else if let Some(capsule) = shape_handle.as_shape::<Capsule<N>>() {
+            DebugShape::Capsule {
+                half_height: capsule.half_height(),
+                radius: capsule.radius(),
+            }
+        } else if let Some(cuboid) = shape_handle.as_shape::<Cuboid<N>>() {
+            DebugShape::Cuboid {
+                half_extents: *cuboid.half_extents(),
+            }
+        } else if let Some(plane) = shape_handle.as_shape::<Plane<N>>() {
+            DebugShape::Plane {
+                normal: *plane.normal(),
+            }
+        } else if let Some(compound) = shape_handle.as_shape::<Compound<N>>() {
+            for (part_position, part_shape_handle) in compound.shapes() {
+                Self::describe(&(position * part_position), part_shape_handle, debug_shapes);
+            }
+            return;
+        } else {
+            DebugShape::Other
+        };
+
+        debug_shapes.push((*position, debug_shape));
+    }
+}
+
 /// The `PhysicsCollider` `Component` represents a `Collider` in the physics
 /// world. A physics `Collider` is automatically created when this `Component`
 /// is added to an `Entity`. Value changes are automatically synchronised with
 /// the physic worlds `Collider`.
+///
+/// As nphysics does not allow changing the shape, density, margin or offset
+/// of an existing `Collider`, modifying any of these values rebuilds the
+/// `Collider`. Ongoing contacts of the `Collider` are dropped and reported
+/// again as new `ContactEvent`s on the next step. All other values, as well
+/// as replacing one `BasicMaterial` with another, are applied to the existing
+/// `Collider`. The shape is changed through `PhysicsCollider::set_shape`.
 #[derive(Clone)]
 pub struct PhysicsCollider<N: RealField> {
     /// The handle to the collider in the physics world.
     pub(crate) handle: Option<DefaultColliderHandle>,
     /// The shape of this collider.
-    pub shape: Shape<N>,
+    pub(crate) shape: Shape<N>,
     /// The position/rotation offset of the collider from the entity it is
     /// attached to.
     pub offset_from_parent: Isometry3<N>,
@@ -161,6 +397,11 @@ pub struct PhysicsCollider<N: RealField> {
     /// Whether this collider is a sensor and only emits events without
     /// interacting (true) or if it is a regular collider (false).
     pub sensor: bool,
+    /// The `ShapeHandle` built from `shape`; cached to avoid rebuilding
+    /// expensive shapes, such as `TriMesh`es, whenever the collider is
+    /// re-inserted into the physics world. `None` until it is built and once
+    /// `shape` was replaced.
+    pub(crate) shape_handle: Option<ShapeHandle<N>>,
 }
 
 impl<N: RealField> Component for PhysicsCollider<N> {
@@ -173,6 +414,7 @@ impl<N: RealField> fmt::Debug for PhysicsCollider<N> {
             f,
             "PhysicsCollider {{ \
              handle: {:?}, \
+             shape: {:?}, \
              offset_from_parent: {:?}, \
              density: {}, \
              margin: {}, \
@@ -182,6 +424,7 @@ impl<N: RealField> fmt::Debug for PhysicsCollider<N> {
              sensor: {} \
              }}",
             self.handle,
+            self.shape,
             self.offset_from_parent,
             self.density,
             self.margin,
@@ -195,11 +438,178 @@ impl<N: RealField> fmt::Debug for PhysicsCollider<N> {
 }
 
 impl<N: RealField> PhysicsCollider<N> {
-    /// Returns the `ShapeHandle` for `shape`, taking the `margin` into
-    /// consideration.
-    pub(crate) fn shape_handle(&self) -> ShapeHandle<N> {
-        self.shape.handle()
+    /// Returns the shape of this collider.
+    pub fn shape(&self) -> &Shape<N> {
+        &self.shape
+    }
+
+    /// Replaces the shape of this collider, which rebuilds its `Collider` on
+    /// the next synchronisation.
+    pub fn set_shape(&mut self, shape: Shape<N>) {
+        self.shape = shape;
+        self.shape_handle = None;
+    }
+
+    /// Returns the `ShapeHandle` for `shape`. The `ShapeHandle` is fetched
+    /// from the given `ShapeCache` and cached until `shape` is replaced.
+    pub(crate) fn shape_handle(&mut self, shape_cache: &mut ShapeCache<N>) -> ShapeHandle<N> {
+        let shape = &self.shape;
+        self.shape_handle
+            .get_or_insert_with(|| shape_cache.handle(shape))
+            .clone()
+    }
+
+    /// Returns whether `shape` was replaced since its `ShapeHandle` was built.
+    pub(crate) fn shape_changed(&self) -> bool {
+        self.shape_handle.is_none()
+    }
+
+    /// Returns a `PhysicsColliderData` describing this collider, or `None` if
+    /// its material is not a `BasicMaterial`.
+    pub fn data(&self) -> Option<PhysicsColliderData<N>> {
+        let material = self.material.downcast_ref::<BasicMaterial<N>>()?;
+        let groups = |contains: &dyn Fn(usize) -> bool| {
+            (0..=CollisionGroups::max_group_id())
+                .filter(|group| contains(*group))
+                .collect()
+        };
+
+        Some(PhysicsColliderData {
+            shape: self.shape.clone(),
+            offset_from_parent: self.offset_from_parent,
+            density: self.density,
+            restitution: material.restitution,
+            friction: material.friction,
+            margin: self.margin,
+            membership: groups(&|group| self.collision_groups.is_member_of(group)),
+            whitelist: groups(&|group| self.collision_groups.is_group_whitelisted(group)),
+            blacklist: groups(&|group| self.collision_groups.is_group_blacklisted(group)),
+            linear_prediction: self.linear_prediction,
+            angular_prediction: self.angular_prediction,
+            sensor: self.sensor,
+        })
+    }
+}
+
+/// `PhysicsColliderData` is a plain data description of a `PhysicsCollider`,
+/// which can be (de)serialized with the `serialize` feature, e.g. for save
+/// games. Materials are limited to `BasicMaterial`s, described by their
+/// restitution and friction, and `Shape::TriMesh`es fail to serialize.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicsColliderData<N: RealField> {
+    pub shape: Shape<N>,
+    pub offset_from_parent: Isometry3<N>,
+    pub density: N,
+    /// The restitution of the `BasicMaterial`.
+    pub restitution: N,
+    /// The friction of the `BasicMaterial`.
+    pub friction: N,
+    pub margin: N,
+    /// The collision groups the collider is a member of.
+    pub membership: Vec<usize>,
+    /// The collision groups the collider interacts with.
+    pub whitelist: Vec<usize>,
+    /// The collision groups the collider never interacts with.
+    pub blacklist: Vec<usize>,
+    pub linear_prediction: N,
+    pub angular_prediction: N,
+    pub sensor: bool,
+}
+
+impl<N: RealField> PhysicsColliderData<N> {
+    /// Builds a `PhysicsCollider` from this description.
+    pub fn build(&self) -> PhysicsCollider<N> {
+        PhysicsColliderBuilder::from(self.shape.clone())
+            .offset_from_parent(self.offset_from_parent)
+            .density(self.density)
+            .material(MaterialHandle::new(BasicMaterial::new(
+                self.restitution,
+                self.friction,
+            )))
+            .margin(self.margin)
+            .member_of(&self.membership)
+            .collides_with(&self.whitelist)
+            .blacklist(&self.blacklist)
+            .linear_prediction(self.linear_prediction)
+            .angular_prediction(self.angular_prediction)
+            .sensor(self.sensor)
+            .build()
+    }
+}
+
+/// The `ShapeCache` `Resource` shares the `ShapeHandle`s built for
+/// `Shape::TriMesh`es between `PhysicsCollider`s, so spawning many colliders
+/// with the same `MeshHandle` only builds the mesh once. Meshes are identified
+/// by their `MeshHandle` rather than their `MeshData`, so looking them up never
+/// generates a mesh. All other shapes are cheap to compare but not always to
+/// build, so they are cached by each `PhysicsCollider` instead.
+///
+/// Cached `ShapeHandle`s are evicted by `ShapeCache::evict_unused` once no
+/// `Shape` refers to their `MeshHandle` anymore, which the
+/// `SyncCollidersToPhysicsSystem` does every frame.
+pub struct ShapeCache<N: RealField> {
+    meshes: HashMap<usize, (MeshHandle<N>, ShapeHandle<N>)>,
+}
+
+impl<N: RealField> Default for ShapeCache<N> {
+    fn default() -> Self {
+        Self {
+            meshes: HashMap::new(),
+        }
+    }
+}
+
+impl<N: RealField> ShapeCache<N> {
+    /// Returns the `ShapeHandle` for the given `Shape`. `ShapeHandle`s of
+    /// `Shape::TriMesh`es are built once per `MeshHandle` and cached, all
+    /// other shapes are built anew.
+    pub fn handle(&mut self, shape: &Shape<N>) -> ShapeHandle<N> {
+        match shape {
+            Shape::TriMesh { handle } => self
+                .meshes
+                .entry(handle.id())
+                .or_insert_with(|| (handle.clone(), shape.handle()))
+                .1
+                .clone(),
+            _ => shape.handle(),
+        }
+    }
+
+    /// Drops the cached `ShapeHandle`s of all meshes whose `MeshHandle` is no
+    /// longer referenced outside of the `ShapeCache`.
+    pub fn evict_unused(&mut self) {
+        self.meshes
+            .retain(|_, (mesh_handle, _)| Arc::strong_count(&mesh_handle.0) > 1);
+    }
+
+    /// Returns the number of cached `ShapeHandle`s.
+    pub fn len(&self) -> usize {
+        self.meshes.len()
     }
+
+    /// Returns whether no `ShapeHandle`s are cached.
+    pub fn is_empty(&self) -> bool {
+        self.meshes.is_empty()
+    }
+
+    /// Drops all cached `ShapeHandle`s; `Collider`s keep using their existing
+    /// `ShapeHandle`s.
+    pub fn clear(&mut self) {
+        self.meshes.clear();
+    }
+}
+
+/// Detaches the `PhysicsCollider` of the given `Entity` from its
+/// `PhysicsBody` by removing it from the `Entity`; the `Collider` is removed
+/// from the physics world on the next synchronisation without touching the
+/// `PhysicsBody` itself. Returns the removed `PhysicsCollider`, which can be
+/// attached again later on.
+pub fn detach_collider<N: RealField>(
+    physics_colliders: &mut WriteStorage<PhysicsCollider<N>>,
+    collider_entity: Entity,
+) -> Option<PhysicsCollider<N>> {
+    physics_colliders.remove(collider_entity)
 }
 
 /// The `PhysicsColliderBuilder` implements the builder pattern for
@@ -289,6 +699,26 @@ impl<N: RealField> PhysicsColliderBuilder<N> {
         self
     }
 
+    /// Makes the `PhysicsCollider` a member of the given collision groups only.
+    pub fn member_of(mut self, groups: &[usize]) -> Self {
+        self.collision_groups.set_membership(groups);
+        self
+    }
+
+    /// Makes the `PhysicsCollider` collide with members of the given
+    /// collision groups only.
+    pub fn collides_with(mut self, groups: &[usize]) -> Self {
+        self.collision_groups.set_whitelist(groups);
+        self
+    }
+
+    /// Prevents the `PhysicsCollider` from colliding with members of the given
+    /// collision groups; the blacklist has priority over the whitelist.
+    pub fn blacklist(mut self, groups: &[usize]) -> Self {
+        self.collision_groups.set_blacklist(groups);
+        self
+    }
+
     /// Sets the `linear_prediction` value of the `PhysicsColliderBuilder`.
     pub fn linear_prediction(mut self, linear_prediction: N) -> Self {
         self.linear_prediction = linear_prediction;
@@ -321,6 +751,199 @@ impl<N: RealField> PhysicsColliderBuilder<N> {
             linear_prediction: self.linear_prediction,
             angular_prediction: self.angular_prediction,
             sensor: self.sensor,
+            shape_handle: None,
+        }
+    }
+}
+
+/// The `SurfaceVelocity` `Component` turns the `PhysicsCollider` of its
+/// `Entity` into a conveyor belt. Bodies touching the collider are dragged
+/// along the contact tangent towards the given velocity, expressed in the
+/// local space of the collider, while the collider itself does not move. See
+/// the `SurfaceVelocitySystem` for details.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SurfaceVelocity<N: RealField>(pub Vector3<N>);
+
+impl<N: RealField> Component for SurfaceVelocity<N> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "serialize")]
+    use crate::colliders::{IntoMesh, MeshData, MeshHandle};
+    use crate::{
+        colliders::Shape,
+        nalgebra::{Isometry3, Vector3},
+        nphysics::material::{BasicMaterial, MaterialHandle},
+        PhysicsColliderBuilder,
+    };
+
+    #[test]
+    fn collision_group_methods() {
+        let physics_collider = PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 })
+            .member_of(&[1])
+            .collides_with(&[2])
+            .blacklist(&[3])
+            .build();
+
+        let collision_groups = physics_collider.collision_groups;
+        assert!(collision_groups.is_member_of(1));
+        assert!(!collision_groups.is_member_of(2));
+        assert!(collision_groups.is_group_whitelisted(2));
+        assert!(!collision_groups.is_group_whitelisted(1));
+        assert!(collision_groups.is_group_blacklisted(3));
+        assert!(!collision_groups.is_group_blacklisted(2));
+    }
+
+    #[test]
+    fn describe_collider() {
+        let physics_collider = PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 })
+            .material(MaterialHandle::new(BasicMaterial::new(0.3, 0.7)))
+            .member_of(&[1, 2])
+            .collides_with(&[3])
+            .sensor(true)
+            .build();
+
+        let data = physics_collider.data().unwrap();
+        assert_eq!(data.restitution, 0.3);
+        assert_eq!(data.friction, 0.7);
+        assert_eq!(data.membership, vec![1, 2]);
+        assert_eq!(data.whitelist, vec![3]);
+        assert!(data.blacklist.is_empty());
+
+        // building a PhysicsCollider from the data describes the same collider
+        let rebuilt = data.build();
+        assert_eq!(rebuilt.data(), Some(data));
+        assert!(rebuilt.collision_groups.is_member_of(2));
+        assert!(!rebuilt.collision_groups.is_group_whitelisted(1));
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn serialize_shapes() {
+        let compound = Shape::<f32>::Compound {
+            parts: vec![(
+                Isometry3::translation(1.0, 0.0, 0.0),
+                Shape::Cuboid {
+                    half_extents: Vector3::new(1.0, 2.0, 3.0),
+                },
+            )],
+        };
+        let serialized = serde_json::to_string(&compound).unwrap();
+        let deserialized: Shape<f32> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, compound);
+
+        // the mesh of a TriMesh is a trait object and can't be serialized
+        #[derive(Clone)]
+        struct Empty;
+
+        impl IntoMesh for Empty {
+            type N = f32;
+
+            fn points(&self) -> MeshData<f32> {
+                (Vec::new(), Vec::new(), None)
+            }
+        }
+
+        let tri_mesh = Shape::TriMesh {
+            handle: MeshHandle::new(Empty),
+        };
+        assert!(serde_json::to_string(&tri_mesh).is_err());
+    }
+
+    #[test]
+    fn compare_shapes() {
+        let cuboid = || Shape::<f32>::Cuboid {
+            half_extents: Vector3::new(1.0, 2.0, 3.0),
+        };
+
+        assert_eq!(cuboid(), cuboid());
+        assert_ne!(cuboid(), Shape::Ball { radius: 1.0 });
+        assert_eq!(
+            format!("{:?}", Shape::<f32>::Ball { radius: 1.0 }),
+            "Ball { radius: 1.0 }"
+        );
+    }
+
+    #[test]
+    fn scale_cuboid() {
+        let cuboid = Shape::<f32>::Cuboid {
+            half_extents: Vector3::new(1.0, 2.0, 3.0),
+        };
+
+        match cuboid.scaled(2.0) {
+            Shape::Cuboid { half_extents } => {
+                assert_eq!(half_extents, Vector3::new(2.0, 4.0, 6.0))
+            }
+            _ => panic!("Scaling changed the type of the Shape"),
+        }
+        match cuboid
+            .scaled_non_uniform(&Vector3::new(1.0, 0.5, 2.0))
+            .unwrap()
+        {
+            Shape::Cuboid { half_extents } => {
+                assert_eq!(half_extents, Vector3::new(1.0, 1.0, 6.0))
+            }
+            _ => panic!("Scaling changed the type of the Shape"),
+        }
+    }
+
+    #[test]
+    fn scale_ball() {
+        let ball = Shape::<f32>::Ball { radius: 1.5 };
+
+        match ball.scaled(2.0) {
+            Shape::Ball { radius } => assert_eq!(radius, 3.0),
+            _ => panic!("Scaling changed the type of the Shape"),
+        }
+        assert!(ball
+            .scaled_non_uniform(&Vector3::new(1.0, 2.0, 1.0))
+            .is_none());
+    }
+
+    #[test]
+    fn scale_compound() {
+        let compound = Shape::<f32>::Compound {
+            parts: vec![(
+                Isometry3::translation(1.0, 0.0, 0.0),
+                Shape::Ball { radius: 1.0 },
+            )],
+        };
+
+        match compound.scaled(2.0) {
+            Shape::Compound { parts } => {
+                assert_eq!(parts[0].0, Isometry3::translation(2.0, 0.0, 0.0));
+                match parts[0].1 {
+                    Shape::Ball { radius } => assert_eq!(radius, 2.0),
+                    _ => panic!("Scaling changed the type of the Shape"),
+                }
+            }
+            _ => panic!("Scaling changed the type of the Shape"),
+        }
+    }
+
+    #[test]
+    fn scale_plane() {
+        let plane = Shape::<f32>::Plane {
+            normal: Vector3::y_axis(),
+        };
+
+        match plane
+            .scaled_non_uniform(&Vector3::new(1.0, 2.0, 1.0))
+            .unwrap()
+        {
+            Shape::Plane { normal } => assert_eq!(normal, Vector3::y_axis()),
+            _ => panic!("Scaling changed the type of the Shape"),
         }
+        match plane.scaled(0.0) {
+            Shape::Plane { normal } => assert_eq!(normal, Vector3::y_axis()),
+            _ => panic!("Scaling changed the type of the Shape"),
+        }
+
+        // a zero component would turn the normal into NaNs
+        assert!(plane
+            .scaled_non_uniform(&Vector3::new(1.0, 0.0, 1.0))
+            .is_none());
     }
 }
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+use specs::{Join, World, WorldExt};
+
+use crate::{
+    bodies::Position,
+    nalgebra::{Point3, RealField, Translation3, Unit, Vector3},
+    ncollide::{bounding_volume::AABB, pipeline::CollisionGroups, query::PointQuery},
+    nphysics::{
+        algebra::{Force3, ForceType},
+        object::{Body, BodyStatus},
+    },
+    Physics,
+};
+
+// Methods manipulating the simulation directly, e.g. for gameplay effects
+impl<N: RealField> Physics<N> {
+    /// Applies an outward impulse to all dynamic `RigidBody`s with a
+    /// `PhysicsCollider` within `radius` of `center`. The impulse points from
+    /// `center` towards the center of mass of each body and falls off linearly
+    /// from `strength` to zero at `radius`. The distance is measured to the
+    /// closest point of the colliders, so bodies straddling the radius are
+    /// affected as well. Static and kinematic bodies are skipped, as are bodies
+    /// whose center of mass coincides with `center`. Explosions with a
+    /// non-positive `radius` have no effect.
+    pub fn apply_explosion(&mut self, center: &Point3<N>, radius: N, strength: N) {
+        if radius <= N::zero() {
+            return;
+        }
+
+        // find the distance of each body to the center via its closest collider;
+        // the broad phase narrows the colliders down to those near the blast
+        let blast = AABB::from_half_extents(*center, Vector3::repeat(radius));
+        let mut distances = HashMap::new();
+        for (_, collider) in self.geometrical_world.interferences_with_aabb(
+            &self.colliders,
+            &blast,
+            &CollisionGroups::new(),
+        ) {
+            let distance = collider
+                .shape()
+                .distance_to_point(collider.position(), center, true);
+            if distance > radius {
+                continue;
+            }
+
+            let closest_distance = distances.entry(collider.body()).or_insert(distance);
+            if distance < *closest_distance {
+                *closest_distance = distance;
+            }
+        }
+
+        for (handle, distance) in distances {
+            let rigid_body = match self.bodies.rigid_body_mut(handle) {
+                Some(rigid_body) if rigid_body.status() == BodyStatus::Dynamic => rigid_body,
+                _ => continue,
+            };
+            let direction =
+                match Unit::try_new(rigid_body.center_of_mass() - center, N::default_epsilon()) {
+                    Some(direction) => direction,
+                    None => continue,
+                };
+
+            let impulse = direction.into_inner() * (strength * (N::one() - distance / radius));
+            rigid_body.apply_force(0, &Force3::linear(impulse), ForceType::Impulse, true);
+        }
+    }
+
+    /// Shifts all `RigidBody`s and all `Collider`s without a parent body by
+    /// `-offset`, moving the origin of the simulation to `offset`. Velocities
+    /// and the sleep state of the `RigidBody`s are retained and joints are
+    /// unaffected, as their anchors are relative to the connected bodies.
+    ///
+    /// This only updates the physics world; use `rebase_origin` to shift the
+    /// `Position`s of all `Entity`s along with it.
+    pub fn rebase_origin(&mut self, offset: &Vector3<N>) {
+        let translation = Translation3::from(-offset);
+        for handle in self.body_handles.values() {
+            if let Some(rigid_body) = self.bodies.rigid_body_mut(*handle) {
+                rigid_body.set_position(translation * rigid_body.position());
+            }
+        }
+
+        // colliders attached to bodies follow their parent body, only colliders
+        // attached to the ground have to be moved explicitly
+        for (_, collider) in self.colliders.iter_mut() {
+            if collider.body() == self.ground {
+                collider.set_position(translation * collider.position());
+            }
+        }
+    }
+}
+
+/// Moves the origin of the world to `offset` by shifting the `Position`s of
+/// all `Entity`s and the physics world by `-offset`, see
+/// `Physics::rebase_origin`. Rebasing the origin regularly keeps the
+/// coordinates around the player small in large worlds, avoiding floating
+/// point precision issues far away from the origin.
+///
+/// All `Position`s are treated as world positions. This must not be called
+/// while the physics `System`s are running.
+pub fn rebase_origin<N, P>(world: &mut World, offset: &Vector3<N>)
+where
+    N: RealField,
+    P: Position<N>,
+{
+    world.write_resource::<Physics<N>>().rebase_origin(offset);
+
+    // the modified Positions match their RigidBodies, so they are not fed back
+    // into the physics world
+    for position in (&mut world.write_storage::<P>()).join() {
+        position.isometry_mut().translation.vector -= offset;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        colliders::Shape,
+        nalgebra::{Isometry3, Point3, Vector3},
+        nphysics::{algebra::Velocity3, object::BodyStatus},
+        physics_dispatcher,
+        Physics,
+        PhysicsBody,
+        PhysicsBodyBuilder,
+        PhysicsColliderBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn rebase_origin() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a static collider and two moving bodies far away from the origin
+        let create_entity = |world: &mut World, x: f32, body_status: Option<BodyStatus>| {
+            let builder = world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(x, 0.0, 0.0)))
+                .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build());
+            match body_status {
+                Some(body_status) => builder
+                    .with(
+                        PhysicsBodyBuilder::<f32>::from(body_status)
+                            .velocity(Velocity3::new(
+                                Vector3::new(0.0, 1.0, 0.0),
+                                Vector3::zeros(),
+                            ))
+                            .build(),
+                    )
+                    .build(),
+                None => builder.build(),
+            }
+        };
+        let wall = create_entity(&mut world, 10_000.0, None);
+        let body1 = create_entity(&mut world, 10_002.0, Some(BodyStatus::Dynamic));
+        let body2 = create_entity(&mut world, 10_005.0, Some(BodyStatus::Dynamic));
+        dispatcher.dispatch(&world);
+
+        super::rebase_origin::<f32, SimplePosition<f32>>(
+            &mut world,
+            &Vector3::new(10_000.0, 0.0, 0.0),
+        );
+        dispatcher.dispatch(&world);
+
+        // the absolute coordinates shrank while the relative distances are preserved
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        let x = |entity| positions.get(entity).unwrap().0.translation.vector.x;
+        assert!(x(wall).abs() < 1.0e-3);
+        assert!((x(body1) - 2.0).abs() < 1.0e-3);
+        assert!((x(body2) - 5.0).abs() < 1.0e-3);
+
+        // the physics world moved along and the bodies kept their velocities
+        let physics = world.read_resource::<Physics<f32>>();
+        let handle = physics.collider_handles.get(&wall.id()).unwrap();
+        let wall_collider = physics.colliders.get(*handle).unwrap();
+        assert!(wall_collider.position().translation.vector.x.abs() < 1.0e-3);
+
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let velocity = physics_bodies.get(body1).unwrap().velocity;
+        assert!((velocity.linear - Vector3::new(0.0, 1.0, 0.0)).norm() < 1.0e-3);
+    }
+
+    #[test]
+    fn apply_explosion() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create balls at increasing distances from the blast point along
+        // different axes, so they don't collide with each other
+        let mut create_ball = |translation: Vector3<f32>, body_status| {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::new(
+                    translation,
+                    Vector3::zeros(),
+                )))
+                .with(PhysicsBodyBuilder::<f32>::from(body_status).build())
+                .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+                .build()
+        };
+        let near = create_ball(Vector3::new(1.0, 0.0, 0.0), BodyStatus::Dynamic);
+        let far = create_ball(Vector3::new(0.0, 2.0, 0.0), BodyStatus::Dynamic);
+        let straddling = create_ball(Vector3::new(0.0, 0.0, 3.0), BodyStatus::Dynamic);
+        let outside = create_ball(Vector3::new(0.0, -5.0, 0.0), BodyStatus::Dynamic);
+        let fixed = create_ball(Vector3::new(-1.0, 0.0, 0.0), BodyStatus::Static);
+        dispatcher.dispatch(&world);
+
+        // let the explosion go off and execute the dispatcher
+        world
+            .write_resource::<Physics<f32>>()
+            .apply_explosion(&Point3::origin(), 3.0, 10.0);
+        dispatcher.dispatch(&world);
+
+        // closer bodies gain more velocity, pointing away from the blast point
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let velocity = |entity| physics_bodies.get(entity).unwrap().velocity.linear;
+        assert!(velocity(near).x > velocity(far).y);
+        assert!(velocity(far).y > velocity(straddling).z);
+        assert!(velocity(straddling).z > 0.0);
+        assert_eq!(velocity(outside), Vector3::zeros());
+        assert_eq!(velocity(fixed), Vector3::zeros());
+    }
+
+    #[test]
+    fn apply_explosion_without_radius() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a ball containing the blast point and execute the dispatcher
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.1, 0.0, 0.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        // an explosion without a radius must not produce NaN impulses
+        world
+            .write_resource::<Physics<f32>>()
+            .apply_explosion(&Point3::origin(), 0.0, 10.0);
+        dispatcher.dispatch(&world);
+
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        assert_eq!(
+            physics_bodies.get(ball).unwrap().velocity.linear,
+            Vector3::zeros()
+        );
+    }
+}
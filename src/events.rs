@@ -1,4 +1,4 @@
-use specs::Entity;
+use specs::{Component, DenseVecStorage, Entity, FlaggedStorage};
 
 use crate::{ncollide::query::Proximity, shrev::EventChannel};
 
@@ -40,3 +40,66 @@ pub struct ProximityEvent {
 /// `ProximityEvent` is a custom `EventChannel` type used to expose
 /// `ProximityEvent`s.
 pub type ProximityEvents = EventChannel<ProximityEvent>;
+
+/// The `Trigger` `Component` turns an `Entity` with a *sensor*
+/// `PhysicsCollider` into a trigger volume. Instead of the pairwise
+/// `ProximityEvent`s, `TriggerEvent`s scoped to the trigger `Entity` are
+/// emitted whenever other colliders enter or exit it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Trigger {
+    /// Whether colliders already overlapping the trigger when it is created
+    /// emit a `TriggerType::Enter` event.
+    pub enter_on_spawn: bool,
+}
+
+impl Component for Trigger {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// The `TriggerType` is set accordingly to whether a collider entered or
+/// exited a trigger volume.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TriggerType {
+    /// Event occurring when a collider starts intersecting the trigger.
+    Enter,
+    /// Event occurring when a collider stops intersecting the trigger.
+    Exit,
+}
+
+/// The `TriggerEvent` type contains the `Trigger` `Entity` and the `Entity`
+/// that entered or exited it.
+#[derive(Debug)]
+pub struct TriggerEvent {
+    pub trigger: Entity,
+    pub entity: Entity,
+
+    pub trigger_type: TriggerType,
+}
+
+/// `TriggerEvents` is a custom `EventChannel` type used to expose
+/// `TriggerEvent`s.
+pub type TriggerEvents = EventChannel<TriggerEvent>;
+
+/// The `JointBroken` event is emitted by the `BreakJointsSystem` once the
+/// `PhysicsJoint` of the contained `Entity` exceeded its break force or
+/// torque and got removed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointBroken(pub Entity);
+
+/// `JointBrokenEvents` is a custom `EventChannel` type used to expose
+/// `JointBroken` events.
+pub type JointBrokenEvents = EventChannel<JointBroken>;
+
+/// The `BodySleepEvent` is emitted by the `PhysicsStepperSystem` whenever the
+/// dynamic `RigidBody` of the contained `Entity` falls asleep or wakes up
+/// during a step. Bodies are considered awake when created; removed bodies
+/// emit no events.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BodySleepEvent {
+    Asleep(Entity),
+    Awake(Entity),
+}
+
+/// `BodySleepEvents` is a custom `EventChannel` type used to expose
+/// `BodySleepEvent`s.
+pub type BodySleepEvents = EventChannel<BodySleepEvent>;
@@ -0,0 +1,170 @@
+use specs::{Component, DenseVecStorage, Entity, FlaggedStorage};
+
+use crate::nalgebra::{Point3, RealField, Vector3};
+
+/// The `DragForce` `Component` applies air drag to the `RigidBody` of its
+/// `Entity`. Before every simulation step a force of `-coefficient * v` is
+/// applied, where `v` is the linear velocity of the `RigidBody` relative to
+/// the surrounding air.
+///
+/// # Example
+///
+/// ```rust
+/// use specs_physics::forces::DragForce;
+///
+/// let drag_force = DragForce::<f32> { coefficient: 0.5 };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DragForce<N: RealField> {
+    pub coefficient: N,
+}
+
+impl<N: RealField> Component for DragForce<N> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The `WindForce` `Component` sets the velocity of the air surrounding the
+/// `RigidBody` of its `Entity`. As the wind acts through air drag, it only
+/// takes effect in combination with a `DragForce`; the `RigidBody` is
+/// accelerated until it moves along with the wind.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WindForce<N: RealField> {
+    pub velocity: Vector3<N>,
+}
+
+impl<N: RealField> Component for WindForce<N> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The `SpringForce` `Component` connects the `RigidBody` of its `Entity` to
+/// the `RigidBody` of `connected_entity` with a damped spring. Before every
+/// simulation step both bodies are pulled towards, or pushed away from, each
+/// other at their anchors until the anchors are `rest_length` apart. The
+/// spring is skipped while `connected_entity` has no `RigidBody`, e.g. after
+/// it has been deleted.
+///
+/// # Example
+///
+/// ```rust
+/// use specs::{Builder, World, WorldExt};
+/// use specs_physics::{forces::SpringForce, nalgebra::Point3};
+///
+/// let mut world = World::new();
+/// let entity = world.create_entity().build();
+///
+/// let spring_force = SpringForce::<f32>::new(entity, 1.0, 10.0)
+///     .with_damping(0.5)
+///     .with_anchors(Point3::new(0.0, 1.0, 0.0), Point3::origin());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpringForce<N: RealField> {
+    pub connected_entity: Entity,
+    /// The distance between both anchors at which the spring exerts no force.
+    pub rest_length: N,
+    /// The force exerted per unit of distance the spring is stretched or
+    /// compressed by.
+    pub stiffness: N,
+    /// The force exerted per unit of velocity both anchors move apart or
+    /// towards each other with; defaults to no damping.
+    pub damping: N,
+    /// The anchor on the `RigidBody` of this `Entity`, in local coordinates.
+    pub local_anchor_1: Point3<N>,
+    /// The anchor on the `RigidBody` of `connected_entity`, in local
+    /// coordinates.
+    pub local_anchor_2: Point3<N>,
+}
+
+impl<N: RealField> SpringForce<N> {
+    /// Creates a new undamped `SpringForce` anchored at the center of both
+    /// `RigidBody`s.
+    pub fn new(connected_entity: Entity, rest_length: N, stiffness: N) -> Self {
+        Self {
+            connected_entity,
+            rest_length,
+            stiffness,
+            damping: N::zero(),
+            local_anchor_1: Point3::origin(),
+            local_anchor_2: Point3::origin(),
+        }
+    }
+
+    /// Sets the `damping` value of the `SpringForce`.
+    pub fn with_damping(mut self, damping: N) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    /// Sets the `local_anchor_1` and `local_anchor_2` values of the
+    /// `SpringForce`.
+    pub fn with_anchors(mut self, local_anchor_1: Point3<N>, local_anchor_2: Point3<N>) -> Self {
+        self.local_anchor_1 = local_anchor_1;
+        self.local_anchor_2 = local_anchor_2;
+        self
+    }
+}
+
+impl<N: RealField> Component for SpringForce<N> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The `Falloff` defines how the force of an `Attractor` decreases with the
+/// distance to the attracted `RigidBody`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Falloff<N: RealField> {
+    /// The force is `strength / distance²`, like gravity between planets.
+    InverseSquare,
+    /// The force decreases linearly from `strength` to zero at `radius`,
+    /// `RigidBody`s further away are not attracted at all.
+    Linear { radius: N },
+}
+
+/// The `Attractor` `Component` pulls all dynamic `RigidBody`s towards the
+/// `Position` of its `Entity` before every simulation step, e.g. to simulate
+/// gravity wells or magnets. Negative strengths push `RigidBody`s away.
+///
+/// # Example
+///
+/// ```rust
+/// use specs_physics::forces::{Attractor, Falloff};
+///
+/// let attractor = Attractor::<f32> {
+///     strength: 100.0,
+///     falloff: Falloff::InverseSquare,
+///     min_distance: 0.5,
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attractor<N: RealField> {
+    pub strength: N,
+    pub falloff: Falloff<N>,
+    /// The distance below which the force no longer grows; avoids the
+    /// singularity of `Falloff::InverseSquare` at the `Attractor`s center.
+    pub min_distance: N,
+}
+
+impl<N: RealField> Component for Attractor<N> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The `BodyGravity` `Component` replaces the world `Gravity` for the
+/// `RigidBody` of its `Entity`, e.g. to simulate localized gravity fields.
+/// The world gravity of the `RigidBody` is disabled and the given
+/// acceleration is applied before every simulation step instead. Removing
+/// the `BodyGravity` enables the world gravity of the `RigidBody` again.
+///
+/// A non-zero `BodyGravity` keeps the `RigidBody` awake, so it never falls
+/// asleep while resting; a zero `BodyGravity` lets it sleep as usual.
+///
+/// # Example
+///
+/// ```rust
+/// use specs_physics::{forces::BodyGravity, nalgebra::Vector3};
+///
+/// let body_gravity = BodyGravity::<f32>(Vector3::new(9.81, 0.0, 0.0));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BodyGravity<N: RealField>(pub Vector3<N>);
+
+impl<N: RealField> Component for BodyGravity<N> {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
@@ -0,0 +1,647 @@
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::{marker::PhantomData, ops::Deref};
+
+use specs::{
+    shred::ResourceId,
+    storage::MaskedStorage,
+    world::Index,
+    BitSet,
+    Component,
+    DenseVecStorage,
+    Entity,
+    FlaggedStorage,
+    Join,
+    NullStorage,
+    ReadStorage,
+    Storage,
+    SystemData,
+    World,
+    WriteStorage,
+};
+
+use crate::{
+    nalgebra::{Isometry3, Point3, RealField, Unit, Vector3},
+    nphysics::joint::DefaultJointConstraintHandle,
+};
+
+/// A `RevoluteJoint` allows the connected bodies to rotate relative to each
+/// other along a single shared axis.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RevoluteJoint<N: RealField> {
+    /// The anchor point of the joint, relative to the first body.
+    pub local_anchor_1: Point3<N>,
+    /// The rotation axis of the joint, relative to the first body.
+    pub local_axis_1: Unit<Vector3<N>>,
+    /// The anchor point of the joint, relative to the second body.
+    pub local_anchor_2: Point3<N>,
+    /// The rotation axis of the joint, relative to the second body.
+    pub local_axis_2: Unit<Vector3<N>>,
+}
+
+/// A `PrismaticJoint` allows the connected bodies to translate relative to
+/// each other along a single axis.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrismaticJoint<N: RealField> {
+    /// The anchor point of the joint, relative to the first body.
+    pub local_anchor_1: Point3<N>,
+    /// The translation axis of the joint, relative to the first body.
+    pub local_axis_1: Unit<Vector3<N>>,
+    /// The anchor point of the joint, relative to the second body.
+    pub local_anchor_2: Point3<N>,
+}
+
+/// A `BallJoint` pins two anchor points together while leaving all rotations
+/// free.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BallJoint<N: RealField> {
+    /// The anchor point of the joint, relative to the first body.
+    pub local_anchor_1: Point3<N>,
+    /// The anchor point of the joint, relative to the second body.
+    pub local_anchor_2: Point3<N>,
+}
+
+/// A `FixedJoint` removes all relative motion between the connected bodies.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedJoint<N: RealField> {
+    /// The anchor frame of the joint, relative to the first body.
+    pub local_anchor_1: Isometry3<N>,
+    /// The anchor frame of the joint, relative to the second body.
+    pub local_anchor_2: Isometry3<N>,
+}
+
+/// `JointType` serves as an abstraction over the nphysics joint constraints,
+/// similar to what `Shape` does for `ShapeHandle`s.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JointType<N: RealField> {
+    Revolute(RevoluteJoint<N>),
+    Prismatic(PrismaticJoint<N>),
+    Ball(BallJoint<N>),
+    Fixed(FixedJoint<N>),
+}
+
+/// A `JointMotor` drives a `RevoluteJoint` or `PrismaticJoint` towards a
+/// desired relative angular or linear velocity between the connected bodies.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointMotor<N: RealField> {
+    /// The relative velocity the motor tries to reach; radians per second for
+    /// `RevoluteJoint`s, units per second for `PrismaticJoint`s.
+    pub desired_velocity: N,
+    /// The maximum torque (`RevoluteJoint`) or force (`PrismaticJoint`) the
+    /// motor is allowed to apply.
+    pub max_force: N,
+    /// Whether the motor is currently driving the joint.
+    pub enabled: bool,
+}
+
+/// `JointLimits` restrict the relative angle of a `RevoluteJoint` or the
+/// relative offset of a `PrismaticJoint`. Other joint types ignore them.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct JointLimits<N: RealField> {
+    /// The lower bound of the angle or offset, if any.
+    pub min: Option<N>,
+    /// The upper bound of the angle or offset, if any.
+    pub max: Option<N>,
+}
+
+/// The `PhysicsJoint` `Component` represents a joint constraint in the physics
+/// world. The joint connects the `PhysicsBody` of the `Entity` it is attached
+/// to with the `PhysicsBody` of the `connected_entity`, or with the ground if
+/// no `connected_entity` is set.
+#[derive(Clone, Copy, Debug)]
+pub struct PhysicsJoint<N: RealField> {
+    /// The handle to the joint constraint in the physics world.
+    pub(crate) handle: Option<DefaultJointConstraintHandle>,
+    /// The `Entity` on the other end of the joint.
+    pub connected_entity: Option<Entity>,
+    /// The type and anchors of this joint.
+    pub joint_type: JointType<N>,
+    /// The motor driving this joint, if any. Can be changed at any time.
+    pub motor: Option<JointMotor<N>>,
+    /// The limits applied to this joint when it is created.
+    pub limits: Option<JointLimits<N>>,
+    /// The force above which this joint breaks, if any. Broken joints are
+    /// removed from their `Entity` by the `BreakJointsSystem`.
+    pub break_force: Option<N>,
+    /// The torque above which this joint breaks, if any.
+    pub break_torque: Option<N>,
+}
+
+impl<N: RealField> PhysicsJoint<N> {
+    /// Returns the `JointMotor` of this joint, if any.
+    pub fn motor(&self) -> Option<&JointMotor<N>> {
+        self.motor.as_ref()
+    }
+
+    /// Returns a mutable reference to the `JointMotor` of this joint, if any.
+    pub fn motor_mut(&mut self) -> Option<&mut JointMotor<N>> {
+        self.motor.as_mut()
+    }
+
+    /// Sets the `JointMotor` driving this joint.
+    pub fn set_motor(&mut self, motor: JointMotor<N>) -> &mut Self {
+        self.motor = Some(motor);
+        self
+    }
+
+    /// Returns a `PhysicsJointData` describing this joint.
+    pub fn data(&self) -> PhysicsJointData<N> {
+        PhysicsJointData {
+            connected_entity: self.connected_entity.map(|entity| entity.id()),
+            joint_type: self.joint_type,
+            motor: self.motor,
+            limits: self.limits,
+            break_force: self.break_force,
+            break_torque: self.break_torque,
+        }
+    }
+}
+
+/// `PhysicsJointData` is a plain data description of a `PhysicsJoint`, which
+/// can be (de)serialized with the `serialize` feature, e.g. for save games.
+/// The `connected_entity` is described by its `Index`, which has to be mapped
+/// to an `Entity` again when building the `PhysicsJoint`.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsJointData<N: RealField> {
+    /// The `Index` of the `Entity` on the other end of the joint.
+    pub connected_entity: Option<Index>,
+    pub joint_type: JointType<N>,
+    pub motor: Option<JointMotor<N>>,
+    pub limits: Option<JointLimits<N>>,
+    pub break_force: Option<N>,
+    pub break_torque: Option<N>,
+}
+
+impl<N: RealField> PhysicsJointData<N> {
+    /// Builds a `PhysicsJoint` from this description, connected to the given
+    /// `Entity` in place of the described `connected_entity`.
+    pub fn build(&self, connected_entity: Option<Entity>) -> PhysicsJoint<N> {
+        PhysicsJoint {
+            handle: None,
+            connected_entity,
+            joint_type: self.joint_type,
+            motor: self.motor,
+            limits: self.limits,
+            break_force: self.break_force,
+            break_torque: self.break_torque,
+        }
+    }
+}
+
+impl<N: RealField> Component for PhysicsJoint<N> {
+    type Storage = FlaggedStorage<Self, DenseVecStorage<Self>>;
+}
+
+/// The `PhysicsJointBuilder` implements the builder pattern for
+/// `PhysicsJoint`s and is the recommended way of instantiating and customising
+/// new `PhysicsJoint` instances.
+///
+/// # Example
+///
+/// ```rust
+/// use specs::{Builder, World, WorldExt};
+/// use specs_physics::{
+///     joints::{JointType, RevoluteJoint},
+///     nalgebra::{Point3, Vector3},
+///     PhysicsJointBuilder,
+/// };
+///
+/// let mut world = World::new();
+/// let other = world.create_entity().build();
+///
+/// let physics_joint = PhysicsJointBuilder::from(JointType::Revolute(RevoluteJoint {
+///     local_anchor_1: Point3::new(0.0, 1.0, 0.0),
+///     local_axis_1: Vector3::z_axis(),
+///     local_anchor_2: Point3::new(0.0, -1.0, 0.0),
+///     local_axis_2: Vector3::z_axis(),
+/// }))
+/// .connected_entity(other)
+/// .build();
+/// ```
+pub struct PhysicsJointBuilder<N: RealField> {
+    connected_entity: Option<Entity>,
+    joint_type: JointType<N>,
+    motor: Option<JointMotor<N>>,
+    limits: Option<JointLimits<N>>,
+    break_force: Option<N>,
+    break_torque: Option<N>,
+}
+
+impl<N: RealField> From<JointType<N>> for PhysicsJointBuilder<N> {
+    /// Creates a new `PhysicsJointBuilder` from the given `JointType`. The
+    /// joint is anchored to the ground unless a `connected_entity` is set.
+    fn from(joint_type: JointType<N>) -> Self {
+        Self {
+            connected_entity: None,
+            joint_type,
+            motor: None,
+            limits: None,
+            break_force: None,
+            break_torque: None,
+        }
+    }
+}
+
+impl<N: RealField> PhysicsJointBuilder<N> {
+    /// Sets the `connected_entity` value of the `PhysicsJointBuilder`.
+    pub fn connected_entity(mut self, connected_entity: Entity) -> Self {
+        self.connected_entity = Some(connected_entity);
+        self
+    }
+
+    /// Sets the `motor` value of the `PhysicsJointBuilder`.
+    pub fn motor(mut self, motor: JointMotor<N>) -> Self {
+        self.motor = Some(motor);
+        self
+    }
+
+    /// Sets the `limits` value of the `PhysicsJointBuilder`.
+    pub fn limits(mut self, limits: JointLimits<N>) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Sets the `break_force` value of the `PhysicsJointBuilder`.
+    pub fn break_force(mut self, break_force: N) -> Self {
+        self.break_force = Some(break_force);
+        self
+    }
+
+    /// Sets the `break_torque` value of the `PhysicsJointBuilder`.
+    pub fn break_torque(mut self, break_torque: N) -> Self {
+        self.break_torque = Some(break_torque);
+        self
+    }
+
+    /// Builds the `PhysicsJoint` from the values set in the
+    /// `PhysicsJointBuilder` instance.
+    pub fn build(self) -> PhysicsJoint<N> {
+        PhysicsJoint {
+            handle: None,
+            connected_entity: self.connected_entity,
+            joint_type: self.joint_type,
+            motor: self.motor,
+            limits: self.limits,
+            break_force: self.break_force,
+            break_torque: self.break_torque,
+        }
+    }
+}
+
+/// Marks an `Entity` whose `PhysicsJoint` is a `RevoluteJoint`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RevoluteMarker;
+
+impl Component for RevoluteMarker {
+    type Storage = NullStorage<Self>;
+}
+
+/// Marks an `Entity` whose `PhysicsJoint` is a `PrismaticJoint`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PrismaticMarker;
+
+impl Component for PrismaticMarker {
+    type Storage = NullStorage<Self>;
+}
+
+/// Marks an `Entity` whose `PhysicsJoint` is a `BallJoint`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BallMarker;
+
+impl Component for BallMarker {
+    type Storage = NullStorage<Self>;
+}
+
+/// Marks an `Entity` whose `PhysicsJoint` is a `FixedJoint`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FixedMarker;
+
+impl Component for FixedMarker {
+    type Storage = NullStorage<Self>;
+}
+
+/// Implemented by the concrete joint types contained in a `JointType`. Ties
+/// each joint type to its marker `Component` and allows `ReadJoints` and
+/// `WriteJoints` to downcast a `PhysicsJoint` to it.
+pub trait JointKind<N: RealField>: Sized + Send + Sync + 'static {
+    type Marker: Component + Default;
+
+    fn downcast(joint_type: &JointType<N>) -> Option<&Self>;
+    fn downcast_mut(joint_type: &mut JointType<N>) -> Option<&mut Self>;
+}
+
+macro_rules! impl_joint_kind {
+    ($joint:ident, $variant:ident, $marker:ident) => {
+        impl<N: RealField> JointKind<N> for $joint<N> {
+            type Marker = $marker;
+
+            fn downcast(joint_type: &JointType<N>) -> Option<&Self> {
+                match joint_type {
+                    JointType::$variant(joint) => Some(joint),
+                    _ => None,
+                }
+            }
+
+            fn downcast_mut(joint_type: &mut JointType<N>) -> Option<&mut Self> {
+                match joint_type {
+                    JointType::$variant(joint) => Some(joint),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_joint_kind!(RevoluteJoint, Revolute, RevoluteMarker);
+impl_joint_kind!(PrismaticJoint, Prismatic, PrismaticMarker);
+impl_joint_kind!(BallJoint, Ball, BallMarker);
+impl_joint_kind!(FixedJoint, Fixed, FixedMarker);
+
+/// `SystemData` giving read access to all `PhysicsJoint`s of the joint type
+/// `C`. Joining over a `&ReadJoints` only yields the `Entity`s carrying the
+/// marker of `C` and hands out the downcasted joint. The markers are only
+/// updated by the `SyncJointsToPhysicsSystem`, so `PhysicsJoint`s whose
+/// `joint_type` changed since are skipped until then.
+///
+/// # Example
+///
+/// ```rust
+/// use specs::{Join, System};
+/// use specs_physics::joints::{ReadJoints, RevoluteJoint};
+///
+/// struct RevoluteJointSystem;
+///
+/// impl<'s> System<'s> for RevoluteJointSystem {
+///     type SystemData = ReadJoints<'s, f32, RevoluteJoint<f32>>;
+///
+///     fn run(&mut self, revolute_joints: Self::SystemData) {
+///         for revolute_joint in (&revolute_joints).join() {
+///             println!("{:?}", revolute_joint.local_axis_1);
+///         }
+///     }
+/// }
+/// ```
+pub struct ReadJoints<'s, N: RealField, C: JointKind<N>> {
+    joints: ReadStorage<'s, PhysicsJoint<N>>,
+    markers: ReadStorage<'s, C::Marker>,
+    c_marker: PhantomData<C>,
+}
+
+/// `SystemData` giving write access to all `PhysicsJoint`s of the joint type
+/// `C`. See `ReadJoints`.
+pub struct WriteJoints<'s, N: RealField, C: JointKind<N>> {
+    joints: WriteStorage<'s, PhysicsJoint<N>>,
+    markers: ReadStorage<'s, C::Marker>,
+    c_marker: PhantomData<C>,
+}
+
+impl<'s, N: RealField, C: JointKind<N>> SystemData<'s> for ReadJoints<'s, N, C> {
+    fn setup(res: &mut World) {
+        <ReadStorage<'s, PhysicsJoint<N>> as SystemData>::setup(res);
+        <ReadStorage<'s, C::Marker> as SystemData>::setup(res);
+    }
+
+    fn fetch(res: &'s World) -> Self {
+        Self {
+            joints: SystemData::fetch(res),
+            markers: SystemData::fetch(res),
+            c_marker: PhantomData,
+        }
+    }
+
+    fn reads() -> Vec<ResourceId> {
+        let mut reads = <ReadStorage<'s, PhysicsJoint<N>> as SystemData>::reads();
+        reads.extend(<ReadStorage<'s, C::Marker> as SystemData>::reads());
+        reads
+    }
+
+    fn writes() -> Vec<ResourceId> {
+        vec![]
+    }
+}
+
+impl<'s, N: RealField, C: JointKind<N>> SystemData<'s> for WriteJoints<'s, N, C> {
+    fn setup(res: &mut World) {
+        <WriteStorage<'s, PhysicsJoint<N>> as SystemData>::setup(res);
+        <ReadStorage<'s, C::Marker> as SystemData>::setup(res);
+    }
+
+    fn fetch(res: &'s World) -> Self {
+        Self {
+            joints: SystemData::fetch(res),
+            markers: SystemData::fetch(res),
+            c_marker: PhantomData,
+        }
+    }
+
+    fn reads() -> Vec<ResourceId> {
+        let mut reads = <WriteStorage<'s, PhysicsJoint<N>> as SystemData>::reads();
+        reads.extend(<ReadStorage<'s, C::Marker> as SystemData>::reads());
+        reads
+    }
+
+    fn writes() -> Vec<ResourceId> {
+        <WriteStorage<'s, PhysicsJoint<N>> as SystemData>::writes()
+    }
+}
+
+// the ids of all PhysicsJoints with a marker of C whose joint type actually is
+// C; a marker may be stale if the joint type was changed since the last run of
+// the SyncJointsToPhysicsSystem
+fn matching_joints<N, C, D>(
+    joints: &Storage<PhysicsJoint<N>, D>,
+    markers: &ReadStorage<C::Marker>,
+) -> BitSet
+where
+    N: RealField,
+    C: JointKind<N>,
+    D: Deref<Target = MaskedStorage<PhysicsJoint<N>>>,
+{
+    (joints, markers.mask())
+        .join()
+        .filter(|(joint, _)| C::downcast(&joint.joint_type).is_some())
+        .map(|(_, id)| id)
+        .collect()
+}
+
+type ReadJointsJoin<'a, 's, N, C> = (
+    &'a ReadStorage<'s, PhysicsJoint<N>>,
+    &'a ReadStorage<'s, <C as JointKind<N>>::Marker>,
+);
+
+type WriteJointsJoin<'a, 's, N, C> = (
+    &'a mut WriteStorage<'s, PhysicsJoint<N>>,
+    &'a ReadStorage<'s, <C as JointKind<N>>::Marker>,
+);
+
+impl<'a, 's, N: RealField, C: JointKind<N>> Join for &'a ReadJoints<'s, N, C> {
+    type Mask = BitSet;
+    type Type = &'a C;
+    type Value = <ReadJointsJoin<'a, 's, N, C> as Join>::Value;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        let mask = matching_joints::<N, C, _>(&self.joints, &self.markers);
+        let (_, value) = (&self.joints, &self.markers).open();
+        (mask, value)
+    }
+
+    unsafe fn get(value: &mut Self::Value, id: Index) -> Self::Type {
+        let (joint, _) = <ReadJointsJoin<'a, 's, N, C> as Join>::get(value, id);
+        C::downcast(&joint.joint_type).expect("Joint type was checked when opening the join.")
+    }
+}
+
+impl<'a, 's, N: RealField, C: JointKind<N>> Join for &'a mut WriteJoints<'s, N, C> {
+    type Mask = BitSet;
+    type Type = &'a mut C;
+    type Value = <WriteJointsJoin<'a, 's, N, C> as Join>::Value;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        let mask = matching_joints::<N, C, _>(&self.joints, &self.markers);
+        let (_, value) = (&mut self.joints, &self.markers).open();
+        (mask, value)
+    }
+
+    unsafe fn get(value: &mut Self::Value, id: Index) -> Self::Type {
+        let (joint, _) = <WriteJointsJoin<'a, 's, N, C> as Join>::get(value, id);
+        C::downcast_mut(&mut joint.joint_type)
+            .expect("Joint type was checked when opening the join.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        joints::{BallJoint, JointType, PhysicsJoint, ReadJoints, RevoluteJoint, WriteJoints},
+        nalgebra::{Isometry3, Point3, Vector3},
+        nphysics::object::BodyStatus,
+        systems::{SyncBodiesToPhysicsSystem, SyncJointsToPhysicsSystem},
+        PhysicsBodyBuilder,
+        PhysicsJointBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn join_revolute_joints() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncJointsToPhysicsSystem::<f32>::default(),
+                "sync_joints_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        dispatcher.setup(&mut world);
+
+        // create two Entities with RevoluteJoints and one with a BallJoint
+        for joint_type in &[
+            JointType::Revolute(RevoluteJoint {
+                local_anchor_1: Point3::origin(),
+                local_axis_1: Vector3::x_axis(),
+                local_anchor_2: Point3::origin(),
+                local_axis_2: Vector3::x_axis(),
+            }),
+            JointType::Ball(BallJoint {
+                local_anchor_1: Point3::origin(),
+                local_anchor_2: Point3::origin(),
+            }),
+            JointType::Revolute(RevoluteJoint {
+                local_anchor_1: Point3::origin(),
+                local_axis_1: Vector3::z_axis(),
+                local_anchor_2: Point3::origin(),
+                local_axis_2: Vector3::z_axis(),
+            }),
+        ] {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+                .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+                .with(PhysicsJointBuilder::from(*joint_type).build())
+                .build();
+        }
+        dispatcher.dispatch(&world);
+
+        // only the RevoluteJoints are joined over and downcasted
+        let revolute_joints = world.system_data::<ReadJoints<f32, RevoluteJoint<f32>>>();
+        let axes: Vec<Vector3<f32>> = (&revolute_joints)
+            .join()
+            .map(|joint| joint.local_axis_1.into_inner())
+            .collect();
+        assert_eq!(axes, vec![Vector3::x(), Vector3::z()]);
+    }
+
+    #[test]
+    fn skip_stale_markers() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncJointsToPhysicsSystem::<f32>::default(),
+                "sync_joints_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with a RevoluteJoint
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(
+                PhysicsJointBuilder::from(JointType::Revolute(RevoluteJoint {
+                    local_anchor_1: Point3::origin(),
+                    local_axis_1: Vector3::x_axis(),
+                    local_anchor_2: Point3::origin(),
+                    local_axis_2: Vector3::x_axis(),
+                }))
+                .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+
+        // change it into a BallJoint without synchronising the markers
+        world
+            .write_storage::<PhysicsJoint<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .joint_type = JointType::Ball(BallJoint {
+            local_anchor_1: Point3::origin(),
+            local_anchor_2: Point3::origin(),
+        });
+
+        // the stale RevoluteMarker doesn't yield the joint
+        {
+            let revolute_joints = world.system_data::<ReadJoints<f32, RevoluteJoint<f32>>>();
+            assert_eq!((&revolute_joints).join().count(), 0);
+        }
+        {
+            let mut revolute_joints = world.system_data::<WriteJoints<f32, RevoluteJoint<f32>>>();
+            assert_eq!((&mut revolute_joints).join().count(), 0);
+        }
+
+        // until the markers are synchronised again
+        dispatcher.dispatch(&world);
+        let ball_joints = world.system_data::<ReadJoints<f32, BallJoint<f32>>>();
+        assert_eq!((&ball_joints).join().count(), 1);
+    }
+}
@@ -0,0 +1,240 @@
+//! # Layers module
+//! Named collision layers compiled to ncollide `CollisionGroups`.
+//!
+//! Instead of configuring the `CollisionGroups` of every `PhysicsCollider` by
+//! hand, layers are declared once in the `CollisionMatrix` `Resource` along
+//! with which layers collide with each other. `Entity`s are then assigned to a
+//! layer via the `Layer` `Component` and the `SyncCollisionLayersSystem`
+//! compiles the effective `CollisionGroups` of their `PhysicsCollider`s.
+//!
+//! # Example
+//!
+//! ```rust
+//! use specs_physics::layers::CollisionMatrix;
+//!
+//! let mut collision_matrix = CollisionMatrix::default();
+//! collision_matrix.add_layer("player");
+//! collision_matrix.add_layer("enemy");
+//! collision_matrix.add_layer("terrain");
+//! collision_matrix.set_collides("player", "terrain", true);
+//! collision_matrix.set_collides("enemy", "terrain", true);
+//!
+//! let player = collision_matrix.collision_groups("player").unwrap();
+//! let enemy = collision_matrix.collision_groups("enemy").unwrap();
+//! let terrain = collision_matrix.collision_groups("terrain").unwrap();
+//! assert!(player.can_interact_with_groups(&terrain));
+//! assert!(!player.can_interact_with_groups(&enemy));
+//! ```
+
+use specs::{Component, DenseVecStorage};
+
+use crate::ncollide::pipeline::CollisionGroups;
+
+/// The `CollisionMatrix` `Resource` maps layer names to the group ids of
+/// ncollide `CollisionGroups` and declares which layers collide with each
+/// other. Collisions are symmetric and disabled by default, including
+/// collisions between members of the same layer.
+///
+/// As ncollide only supports the group ids `0` to
+/// `CollisionGroups::max_group_id()`, at most 30 layers can be declared.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CollisionMatrix {
+    layers: Vec<String>,
+    // bitmask of the layers each layer collides with, indexed by group id
+    collisions: Vec<u32>,
+}
+
+impl CollisionMatrix {
+    /// Declares a new layer and returns its group id. Declaring an existing
+    /// layer again returns its existing group id. Returns `None` if the
+    /// maximum number of layers supported by ncollide has been reached.
+    pub fn add_layer(&mut self, name: &str) -> Option<usize> {
+        if let Some(group_id) = self.group_id(name) {
+            return Some(group_id);
+        }
+
+        let group_id = self.layers.len();
+        if group_id > CollisionGroups::max_group_id() {
+            warn!(
+                "Cannot add collision layer {}, at most {} layers are supported",
+                name,
+                CollisionGroups::max_group_id() + 1
+            );
+            return None;
+        }
+
+        self.layers.push(name.to_string());
+        self.collisions.push(0);
+        Some(group_id)
+    }
+
+    /// Returns the group id of the given layer, if it has been declared.
+    pub fn group_id(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer == name)
+    }
+
+    /// Sets whether the two given layers collide with each other. Passing the
+    /// same layer twice sets whether members of the layer collide with each
+    /// other. Undeclared layers are ignored.
+    pub fn set_collides(&mut self, layer1: &str, layer2: &str, collides: bool) {
+        let (group_id1, group_id2) = match (self.group_id(layer1), self.group_id(layer2)) {
+            (Some(group_id1), Some(group_id2)) => (group_id1, group_id2),
+            _ => {
+                warn!(
+                    "Cannot set collisions between undeclared collision layers {} and {}",
+                    layer1, layer2
+                );
+                return;
+            }
+        };
+
+        if collides {
+            self.collisions[group_id1] |= 1 << group_id2;
+            self.collisions[group_id2] |= 1 << group_id1;
+        } else {
+            self.collisions[group_id1] &= !(1 << group_id2);
+            self.collisions[group_id2] &= !(1 << group_id1);
+        }
+    }
+
+    /// Returns whether the two given layers collide with each other.
+    pub fn collides(&self, layer1: &str, layer2: &str) -> bool {
+        match (self.group_id(layer1), self.group_id(layer2)) {
+            (Some(group_id1), Some(group_id2)) => {
+                self.collisions[group_id1] & (1 << group_id2) != 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Compiles the `CollisionGroups` of the given layer; the resulting
+    /// `CollisionGroups` are a member of the layer only and whitelist all
+    /// layers it collides with. Returns `None` for undeclared layers.
+    pub fn collision_groups(&self, name: &str) -> Option<CollisionGroups> {
+        let group_id = self.group_id(name)?;
+        let whitelist = (0..self.layers.len())
+            .filter(|other| self.collisions[group_id] & (1 << other) != 0)
+            .collect::<Vec<_>>();
+
+        Some(
+            CollisionGroups::new()
+                .with_membership(&[group_id])
+                .with_whitelist(&whitelist),
+        )
+    }
+}
+
+/// The `Layer` `Component` assigns the `PhysicsCollider` of its `Entity` to
+/// a layer of the `CollisionMatrix`. The `collision_groups` of the
+/// `PhysicsCollider` are overwritten with the `CollisionGroups` compiled from
+/// the `CollisionMatrix`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Layer(pub &'static str);
+
+impl Component for Layer {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Returns whether the two given `CollisionGroups` are equal, as
+/// `CollisionGroups` do not implement `PartialEq`.
+pub(crate) fn same_collision_groups(
+    collision_groups1: &CollisionGroups,
+    collision_groups2: &CollisionGroups,
+) -> bool {
+    collision_groups1.can_interact_with_self() == collision_groups2.can_interact_with_self()
+        && (0..=CollisionGroups::max_group_id()).all(|group_id| {
+            collision_groups1.is_member_of(group_id) == collision_groups2.is_member_of(group_id)
+                && collision_groups1.is_group_whitelisted(group_id)
+                    == collision_groups2.is_group_whitelisted(group_id)
+                && collision_groups1.is_group_blacklisted(group_id)
+                    == collision_groups2.is_group_blacklisted(group_id)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use super::{CollisionMatrix, Layer};
+    use crate::{
+        all_physics_dispatcher,
+        colliders::Shape,
+        nalgebra::{Isometry3, Vector3},
+        ncollide::pipeline::CollisionGroups,
+        nphysics::object::BodyStatus,
+        Physics,
+        PhysicsBodyBuilder,
+        PhysicsColliderBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn layer_limit() {
+        let mut collision_matrix = CollisionMatrix::default();
+        for group_id in 0..=CollisionGroups::max_group_id() {
+            let name = format!("layer{}", group_id);
+            assert_eq!(collision_matrix.add_layer(&name), Some(group_id));
+        }
+
+        // existing layers can still be looked up, but no new ones can be added
+        assert_eq!(collision_matrix.add_layer("layer0"), Some(0));
+        assert_eq!(collision_matrix.add_layer("one_too_many"), None);
+        assert!(collision_matrix.collision_groups("one_too_many").is_none());
+    }
+
+    #[test]
+    fn compile_layers() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // players and enemies collide with the terrain but pass through each other
+        {
+            let mut collision_matrix = world.write_resource::<CollisionMatrix>();
+            collision_matrix.add_layer("player");
+            collision_matrix.add_layer("enemy");
+            collision_matrix.add_layer("terrain");
+            collision_matrix.set_collides("player", "terrain", true);
+            collision_matrix.set_collides("enemy", "terrain", true);
+        }
+
+        // create a terrain collider with an overlapping player and enemy on top
+        let terrain = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 1.0, 10.0),
+                })
+                .build(),
+            )
+            .with(Layer("terrain"))
+            .build();
+        let mut create_character = |x: f32, layer: &'static str| {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(x, 1.9, 0.0)))
+                .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+                .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+                .with(Layer(layer))
+                .build()
+        };
+        let player = create_character(0.0, "player");
+        let enemy = create_character(0.5, "enemy");
+        dispatcher.dispatch(&world);
+
+        // the player only touches the terrain
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let contacts = physics
+            .contacts_with(&entities, player, false)
+            .collect::<Vec<_>>();
+        assert_eq!(contacts, vec![terrain]);
+
+        // the enemy only touches the terrain as well
+        let contacts = physics
+            .contacts_with(&entities, enemy, false)
+            .collect::<Vec<_>>();
+        assert_eq!(contacts, vec![terrain]);
+    }
+}
@@ -11,7 +11,12 @@
 //! **specs-physics** defines a set of [Specs][] `System`s and `Component`s to
 //! handle the creation, modification and removal of [nphysics][] objects
 //! ([RigidBody][], [Collider][]) and the synchronisation of object positions
-//! and global gravity between both worlds.
+//! and global gravity between both worlds. The commonly used types are
+//! re-exported by the `prelude` module:
+//!
+//! ```rust
+//! use specs_physics::prelude::*;
+//! ```
 //!
 //! ### Generic types
 //!
@@ -135,9 +140,44 @@
 //! To assign multiple [Collider][]'s the the same body, [Entity hierarchy][]
 //! can be used. This utilises [specs-hierarchy][].
 //!
+//! ##### PhysicsJoint
+//!
+//! `specs_physics::PhysicsJoint`s connect the `PhysicsBody` of their `Entity`
+//! with the `PhysicsBody` of a `connected_entity`, or with the ground if none
+//! is set. They are used to define and create joint constraints in
+//! [nphysics][].
+//!
+//! Example:
+//!
+//! ```rust
+//! use specs_physics::{
+//!     joints::{JointType, RevoluteJoint},
+//!     nalgebra::{Point3, Vector3},
+//!     PhysicsJointBuilder,
+//! };
+//!
+//! let physics_joint = PhysicsJointBuilder::<f32>::from(JointType::Revolute(RevoluteJoint {
+//!     local_anchor_1: Point3::new(0.0, 1.0, 0.0),
+//!     local_axis_1: Vector3::z_axis(),
+//!     local_anchor_2: Point3::origin(),
+//!     local_axis_2: Vector3::z_axis(),
+//! }))
+//! .build();
+//! ```
+//!
+//! Each `Entity` with a `PhysicsJoint` is also marked with the marker
+//! `Component` of its joint type, e.g. `RevoluteMarker`, which allows joining
+//! over a single joint type via `ReadJoints` and `WriteJoints`. The
+//! `EntityBuilderExt::with_joint` method adds both at once.
+//!
+//! Revolute and prismatic joints can be restricted by `JointLimits` and driven
+//! by a `JointMotor`, both configured through the `PhysicsJointBuilder`. The
+//! motor of an existing `PhysicsJoint` can be changed at any time via
+//! `PhysicsJoint::set_motor`.
+//!
 //! ### Systems
 //!
-//! The following `System`s currently exist and should be added to your
+//! The following `System`s are required and should be added to your
 //! `Dispatcher` in order:
 //!
 //! 1. `specs_physics::systems::SyncBodiesToPhysicsSystem` - handles the
@@ -150,19 +190,72 @@
 //! `PhysicsCollider` `Component`. This `System` depends on
 //! `SyncBodiesToPhysicsSystem` as [Collider][] can depend on [RigidBody][].
 //!
-//! 3. `specs_physics::systems::SyncParametersToPhysicsSystem` - handles the
+//! 3. `specs_physics::systems::SyncJointsToPhysicsSystem` - handles the
+//! creation, modification and removal of joint constraints based on the
+//! `PhysicsJoint` `Component` and drives their `JointMotor`s. This `System`
+//! depends on `SyncBodiesToPhysicsSystem` as joints connect [RigidBody][]'s.
+//!
+//! 4. `specs_physics::systems::SyncParametersToPhysicsSystem` - handles the
 //! modification of the [nphysics][] `DefaultMechanicalWorld`s parameters.
 //!
-//! 4. `specs_physics::systems::PhysicsStepperSystem` - handles the progression
+//! 5. `specs_physics::systems::PhysicsStepperSystem` - handles the progression
 //! of the [nphysics][] `DefaultMechanicalWorld` and causes objects to actually
 //! move and change their position. This `System` is the backbone for collision
 //! detection.
 //!
-//! 5. `specs_physics::systems::SyncBodiesFromPhysicsSystem` -
+//! 6. `specs_physics::systems::SyncBodiesFromPhysicsSystem` -
 //! handles the synchronisation of [RigidBody][] positions and dynamics back
 //! into the [Specs][] `Component`s. This `System` also utilises the
 //! `Position` *trait* implementation.
 //!
+//! The following `System`s are optional and only have to be added if you use
+//! the features they implement:
+//!
+//! - `specs_physics::systems::SyncCollisionLayersSystem` - compiles the
+//! `CollisionGroups` of `PhysicsCollider`s with a `Layer` `Component` from the
+//! `CollisionMatrix` `Resource`. Add it before the
+//! `SyncCollidersToPhysicsSystem` and make the latter depend on it.
+//!
+//! - `specs_physics::systems::ApplyForcesSystem` - applies the continuous
+//! forces of the `forces` module, such as `DragForce` and `Attractor`, to
+//! their [RigidBody][]'s. Add it after the `SyncBodiesToPhysicsSystem` and
+//! make the `PhysicsStepperSystem` depend on it.
+//!
+//! - `specs_physics::systems::RaycastWheelSystem` - applies the suspension
+//! forces of `RaycastWheel`s to their chassis [RigidBody][]'s. Add it after the
+//! `SyncBodiesToPhysicsSystem` and the `SyncCollidersToPhysicsSystem` and make
+//! the `PhysicsStepperSystem` depend on it.
+//!
+//! - `specs_physics::systems::CharacterControllerSystem` - moves the kinematic
+//! [RigidBody][]'s of `CharacterController`s along the ground. Add it after the
+//! `SyncBodiesToPhysicsSystem`, the `SyncCollidersToPhysicsSystem` and the
+//! `SyncParametersToPhysicsSystem` and make the `PhysicsStepperSystem` depend
+//! on it.
+//!
+//! - `specs_physics::systems::EnergyAdaptiveStepSystem` - writes the `TimeStep`
+//! picked by the `EnergyAdaptiveStep` `Resource` for the current kinetic
+//! energy. Add it after the `SyncBodiesToPhysicsSystem` and make the
+//! `PhysicsStepperSystem` and the `CharacterControllerSystem` depend on it.
+//!
+//! - `specs_physics::systems::ClampVelocitiesSystem` - caps the velocities of
+//! [RigidBody][]'s with a `MaxVelocity` `Component` after every step. Add it
+//! after the `PhysicsStepperSystem` and make the `SyncBodiesFromPhysicsSystem`
+//! depend on it.
+//!
+//! - `specs_physics::systems::SurfaceVelocitySystem` - carries the
+//! [RigidBody][]'s touching colliders with a `SurfaceVelocity` `Component`
+//! along, like conveyor belts. Add it after the `PhysicsStepperSystem` and make
+//! the `SyncBodiesFromPhysicsSystem` depend on it.
+//!
+//! - `specs_physics::systems::TriggerEventsSystem` - maps the proximity events
+//! of `Trigger` `Entity`s to `TriggerEvents`. Add it after the
+//! `PhysicsStepperSystem`.
+//!
+//! - `specs_physics::systems::BreakJointsSystem` - removes the `PhysicsJoint`s
+//! that broke during the last step and emits `JointBroken` events. Add it
+//! after the `PhysicsStepperSystem`; it is needed as soon as a `PhysicsJoint`
+//! has a `break_force` or `break_torque`.
+//!
 //! An example `Dispatcher` with all required `System`s:
 //!
 //! ```rust
@@ -173,6 +266,7 @@
 //!         SyncBodiesFromPhysicsSystem,
 //!         SyncBodiesToPhysicsSystem,
 //!         SyncCollidersToPhysicsSystem,
+//!         SyncJointsToPhysicsSystem,
 //!         SyncParametersToPhysicsSystem,
 //!     },
 //!     SimplePosition,
@@ -190,6 +284,11 @@
 //!         &["sync_bodies_to_physics_system"],
 //!     )
 //!     .with(
+//!         SyncJointsToPhysicsSystem::<f32>::default(),
+//!         "sync_joints_to_physics_system",
+//!         &["sync_bodies_to_physics_system"],
+//!     )
+//!     .with(
 //!         SyncParametersToPhysicsSystem::<f32>::default(),
 //!         "sync_gravity_to_physics_system",
 //!         &[],
@@ -200,6 +299,7 @@
 //!         &[
 //!             "sync_bodies_to_physics_system",
 //!             "sync_colliders_to_physics_system",
+//!             "sync_joints_to_physics_system",
 //!             "sync_gravity_to_physics_system",
 //!         ],
 //!     )
@@ -230,6 +330,28 @@
 //! `DispatcherBuilder` as an argument and registers the required `System`s for
 //! you.
 //!
+//! Their counterparts `specs_physics::all_physics_dispatcher()` and
+//! `specs_physics::register_all_physics_systems()` additionally register all
+//! optional `System`s. Optional `System`s running after the
+//! `PhysicsStepperSystem` can also be added on top of the required ones:
+//!
+//! ```rust
+//! use specs::DispatcherBuilder;
+//! use specs_physics::{register_physics_systems, systems::TriggerEventsSystem, SimplePosition};
+//!
+//! let mut dispatcher_builder = DispatcherBuilder::new();
+//! register_physics_systems::<f32, SimplePosition<f32>>(&mut dispatcher_builder);
+//! dispatcher_builder.add(
+//!     TriggerEventsSystem::default(),
+//!     "trigger_events_system",
+//!     &["physics_stepper_system"],
+//! );
+//! let dispatcher = dispatcher_builder.build();
+//! ```
+//!
+//! The optional `System`s running before the `PhysicsStepperSystem` have to be
+//! added in between the required ones, as in the example `Dispatcher` above.
+//!
 //! [Specs]: https://slide-rs.github.io/specs/
 //! [nphysics]: https://www.nphysics.org/
 //! [nalgebra]: https://nalgebra.org/
@@ -261,19 +383,25 @@ use specs::{
 use specs_hierarchy::Parent;
 
 pub use self::{
-    bodies::{util::SimplePosition, PhysicsBody, PhysicsBodyBuilder},
+    bodies::{util::SimplePosition, PhysicsBody, PhysicsBodyBuilder, PositionSyncDisabled},
+    builder::EntityBuilderExt,
     colliders::{PhysicsCollider, PhysicsColliderBuilder},
+    effects::rebase_origin,
+    joints::{PhysicsJoint, PhysicsJointBuilder},
+    queries::GroundInfo,
 };
 
 use self::{
     bodies::Position,
-    nalgebra::{RealField, Vector3},
+    nalgebra::{self as na, RealField, Vector3},
     nphysics::{
         counters::Counters,
         force_generator::DefaultForceGeneratorSet,
-        joint::DefaultJointConstraintSet,
+        joint::{DefaultJointConstraintHandle, DefaultJointConstraintSet},
         material::MaterialsCoefficientsTable,
         object::{
+            Body,
+            BodyStatus,
             DefaultBodyHandle,
             DefaultBodySet,
             DefaultColliderHandle,
@@ -284,21 +412,43 @@ use self::{
         world::{DefaultGeometricalWorld, DefaultMechanicalWorld},
     },
     systems::{
+        ApplyForcesSystem,
+        BreakJointsSystem,
+        CharacterControllerSystem,
+        ClampVelocitiesSystem,
+        EnergyAdaptiveStepSystem,
         PhysicsStepperSystem,
+        RaycastWheelSystem,
+        SurfaceVelocitySystem,
         SyncBodiesFromPhysicsSystem,
         SyncBodiesToPhysicsSystem,
         SyncCollidersToPhysicsSystem,
+        SyncCollisionLayersSystem,
+        SyncJointsToPhysicsSystem,
         SyncParametersToPhysicsSystem,
+        TriggerEventsSystem,
     },
 };
 
 #[cfg(feature = "amethyst")]
 pub mod amethyst;
 pub mod bodies;
+mod builder;
+pub mod character;
 pub mod colliders;
+mod effects;
 pub mod events;
+pub mod forces;
+pub mod joints;
+pub mod layers;
 pub mod parameters;
+pub mod prelude;
+mod queries;
+pub mod ragdoll;
+pub mod scene;
+pub mod snapshot;
 pub mod systems;
+pub mod vehicles;
 /// Resource holding the internal fields where physics computation occurs.
 /// Some inspection methods are exposed to allow debugging.
 pub struct Physics<N: RealField> {
@@ -317,6 +467,9 @@ pub struct Physics<N: RealField> {
     /// Hashmap of Entities to internal Collider handles.
     /// Necessary for reacting to removed Components.
     pub(crate) collider_handles: HashMap<Index, DefaultColliderHandle>,
+    /// Hashmap of Entities to internal joint constraint handles.
+    /// Necessary for reacting to removed Components.
+    pub(crate) joint_handles: HashMap<Index, DefaultJointConstraintHandle>,
 }
 
 // Some non-mutating methods for diagnostics and testing
@@ -338,6 +491,27 @@ impl<N: RealField> Physics<N> {
         &self.mechanical_world.gravity
     }
 
+    /// Estimates the total kinetic energy of all dynamic `RigidBody`s from
+    /// their linear and angular velocities, e.g. for picking the `TimeStep`
+    /// with an `EnergyAdaptiveStep`.
+    pub fn kinetic_energy(&self) -> N {
+        let half: N = na::convert(0.5);
+        self.body_handles
+            .values()
+            .filter_map(|handle| self.bodies.rigid_body(*handle))
+            .filter(|rigid_body| rigid_body.status() == BodyStatus::Dynamic)
+            .fold(N::zero(), |energy, rigid_body| {
+                // the angular inertia is expressed in the local space of the body
+                let inertia = rigid_body.local_inertia();
+                let velocity = rigid_body.velocity();
+                let angular_velocity = rigid_body.position().rotation.inverse() * velocity.angular;
+                energy
+                    + half
+                        * (inertia.linear * velocity.linear.norm_squared()
+                            + angular_velocity.dot(&(inertia.angular * angular_velocity)))
+            })
+    }
+
     /// Retrieves the performance statistics for the last simulated timestep.
     /// Profiling is disabled by default.
     /// See also `PhysicsProfilingEnabled` for enabling performance counters.
@@ -372,6 +546,7 @@ impl<N: RealField> Default for Physics<N> {
             force_generators: DefaultForceGeneratorSet::new(),
             body_handles: HashMap::new(),
             collider_handles: HashMap::new(),
+            joint_handles: HashMap::new(),
         }
     }
 }
@@ -412,9 +587,32 @@ where
     dispatcher_builder.build()
 }
 
+/// Convenience function for configuring and building a `Dispatcher` with all
+/// required and all optional physics related `System`s.
+///
+/// # Examples
+/// ```
+/// use specs_physics::bodies::util::SimplePosition;
+/// let dispatcher = specs_physics::all_physics_dispatcher::<f32, SimplePosition<f32>>();
+/// ```
+pub fn all_physics_dispatcher<'a, 'b, N, P>() -> Dispatcher<'a, 'b>
+where
+    N: RealField,
+    P: Position<N>,
+{
+    let mut dispatcher_builder = DispatcherBuilder::new();
+    register_all_physics_systems::<N, P>(&mut dispatcher_builder);
+
+    dispatcher_builder.build()
+}
+
 /// Convenience function for registering all required physics related `System`s
 /// to the given `DispatcherBuilder`. This also serves as a blueprint on how
-///// to properly set up the `System`s and have them depend on each other.
+/// to properly set up the `System`s and have them depend on each other.
+///
+/// The optional `System`s, such as the `ApplyForcesSystem` or the
+/// `TriggerEventsSystem`, are not registered; add the ones you need yourself as
+/// described in the crate documentation or use `register_all_physics_systems`.
 pub fn register_physics_systems<N, P>(dispatcher_builder: &mut DispatcherBuilder)
 where
     N: RealField,
@@ -436,6 +634,14 @@ where
         &["sync_bodies_to_physics_system"],
     );
 
+    // add SyncJointsToPhysicsSystem next with SyncBodiesToPhysicsSystem as its
+    // dependency; joints connect bodies, which have to exist first
+    dispatcher_builder.add(
+        SyncJointsToPhysicsSystem::<N>::default(),
+        "sync_joints_to_physics_system",
+        &["sync_bodies_to_physics_system"],
+    );
+
     // add SyncParametersToPhysicsSystem; this System can be added at any point in
     // time as it merely synchronizes the simulation parameters of the world,
     // thus it has no other dependencies.
@@ -455,6 +661,7 @@ where
         &[
             "sync_bodies_to_physics_system",
             "sync_colliders_to_physics_system",
+            "sync_joints_to_physics_system",
             "sync_parameters_to_physics_system",
         ],
     );
@@ -468,3 +675,241 @@ where
         &["physics_stepper_system"],
     );
 }
+
+/// Convenience function for registering all required and all optional physics
+/// related `System`s to the given `DispatcherBuilder`. This enables every
+/// feature of **specs-physics** at the cost of running `System`s whose
+/// `Component`s might never be used.
+pub fn register_all_physics_systems<N, P>(dispatcher_builder: &mut DispatcherBuilder)
+where
+    N: RealField,
+    P: Position<N>,
+{
+    // add SyncBodiesToPhysicsSystem first since we have to start with bodies;
+    // colliders can exist without a body but in most cases have a body parent
+    dispatcher_builder.add(
+        SyncBodiesToPhysicsSystem::<N, P>::default(),
+        "sync_bodies_to_physics_system",
+        &[],
+    );
+
+    // add SyncCollisionLayersSystem before the SyncCollidersToPhysicsSystem as it
+    // compiles the CollisionGroups of the PhysicsColliders to synchronise
+    dispatcher_builder.add(
+        SyncCollisionLayersSystem::<N>::default(),
+        "sync_collision_layers_system",
+        &[],
+    );
+
+    // add SyncCollidersToPhysicsSystem next with SyncBodiesToPhysicsSystem and
+    // SyncCollisionLayersSystem as its dependencies
+    dispatcher_builder.add(
+        SyncCollidersToPhysicsSystem::<N, P>::default(),
+        "sync_colliders_to_physics_system",
+        &[
+            "sync_bodies_to_physics_system",
+            "sync_collision_layers_system",
+        ],
+    );
+
+    // add SyncJointsToPhysicsSystem next with SyncBodiesToPhysicsSystem as its
+    // dependency; joints connect bodies, which have to exist first
+    dispatcher_builder.add(
+        SyncJointsToPhysicsSystem::<N>::default(),
+        "sync_joints_to_physics_system",
+        &["sync_bodies_to_physics_system"],
+    );
+
+    // add SyncParametersToPhysicsSystem; this System can be added at any point in
+    // time as it merely synchronizes the simulation parameters of the world,
+    // thus it has no other dependencies.
+    dispatcher_builder.add(
+        SyncParametersToPhysicsSystem::<N>::default(),
+        "sync_parameters_to_physics_system",
+        &[],
+    );
+
+    // add ApplyForcesSystem next with SyncBodiesToPhysicsSystem as its
+    // dependency; forces are applied to existing bodies right before stepping
+    dispatcher_builder.add(
+        ApplyForcesSystem::<N, P>::default(),
+        "apply_forces_system",
+        &["sync_bodies_to_physics_system"],
+    );
+
+    // add RaycastWheelSystem next with SyncBodiesToPhysicsSystem and
+    // SyncCollidersToPhysicsSystem as its dependencies; the suspension rays are
+    // cast against the colliders and push the bodies right before stepping
+    dispatcher_builder.add(
+        RaycastWheelSystem::<N>::default(),
+        "raycast_wheel_system",
+        &[
+            "sync_bodies_to_physics_system",
+            "sync_colliders_to_physics_system",
+        ],
+    );
+
+    // add EnergyAdaptiveStepSystem next with SyncBodiesToPhysicsSystem as its
+    // dependency; the TimeStep is picked for the velocities of the synchronised
+    // bodies
+    dispatcher_builder.add(
+        EnergyAdaptiveStepSystem::<N>::default(),
+        "energy_adaptive_step_system",
+        &["sync_bodies_to_physics_system"],
+    );
+
+    // add CharacterControllerSystem next with SyncBodiesToPhysicsSystem,
+    // SyncCollidersToPhysicsSystem, SyncParametersToPhysicsSystem and
+    // EnergyAdaptiveStepSystem as its dependencies; the capsules are swept
+    // against the colliders and move with the gravity and TimeStep of the next
+    // step
+    dispatcher_builder.add(
+        CharacterControllerSystem::<N>::default(),
+        "character_controller_system",
+        &[
+            "sync_bodies_to_physics_system",
+            "sync_colliders_to_physics_system",
+            "sync_parameters_to_physics_system",
+            "energy_adaptive_step_system",
+        ],
+    );
+
+    // add PhysicsStepperSystem after all other Systems that write data to the
+    // nphysics DefaultMechanicalWorld and has to depend on them; this System is
+    // used to progress the nphysics DefaultMechanicalWorld for all existing
+    // objects
+    dispatcher_builder.add(
+        PhysicsStepperSystem::<N>::default(),
+        "physics_stepper_system",
+        &[
+            "sync_bodies_to_physics_system",
+            "sync_colliders_to_physics_system",
+            "sync_joints_to_physics_system",
+            "sync_parameters_to_physics_system",
+            "apply_forces_system",
+            "raycast_wheel_system",
+            "character_controller_system",
+            "energy_adaptive_step_system",
+        ],
+    );
+
+    // add ClampVelocitiesSystem after the PhysicsStepperSystem as it caps the
+    // velocities resulting from the last step
+    dispatcher_builder.add(
+        ClampVelocitiesSystem::<N>::default(),
+        "clamp_velocities_system",
+        &["physics_stepper_system"],
+    );
+
+    // add SurfaceVelocitySystem after the PhysicsStepperSystem as it carries
+    // along the bodies touching surfaces during the last step
+    dispatcher_builder.add(
+        SurfaceVelocitySystem::<N>::default(),
+        "surface_velocity_system",
+        &["physics_stepper_system"],
+    );
+
+    // add SyncBodiesFromPhysicsSystem last as it handles the
+    // synchronisation between nphysics DefaultMechanicalWorld bodies and the
+    // Position components; this depends on the PhysicsStepperSystem, the
+    // ClampVelocitiesSystem and the SurfaceVelocitySystem
+    dispatcher_builder.add(
+        SyncBodiesFromPhysicsSystem::<N, P>::default(),
+        "sync_bodies_from_physics_system",
+        &[
+            "physics_stepper_system",
+            "clamp_velocities_system",
+            "surface_velocity_system",
+        ],
+    );
+
+    // add TriggerEventsSystem after the PhysicsStepperSystem as it maps the
+    // ProximityEvents emitted during the last step to TriggerEvents
+    dispatcher_builder.add(
+        TriggerEventsSystem::default(),
+        "trigger_events_system",
+        &["physics_stepper_system"],
+    );
+
+    // add BreakJointsSystem after the PhysicsStepperSystem as it removes the
+    // PhysicsJoints broken during the last step
+    dispatcher_builder.add(
+        BreakJointsSystem::<N>::default(),
+        "break_joints_system",
+        &["physics_stepper_system"],
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        all_physics_dispatcher,
+        events::{ContactEvents, ProximityEvents, TriggerEvents},
+        nalgebra::{Isometry3, Vector3},
+        nphysics::object::BodyStatus,
+        parameters::Gravity,
+        physics_dispatcher,
+        register_physics_systems,
+        PhysicsBodyBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn register_systems_by_hand() {
+        let mut world = World::new();
+        let mut dispatcher_builder = DispatcherBuilder::new();
+        register_physics_systems::<f32, SimplePosition<f32>>(&mut dispatcher_builder);
+        let mut dispatcher = dispatcher_builder.build();
+        dispatcher.setup(&mut world);
+
+        // create a falling Entity with gravity pointing down
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 5.0, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .build();
+        for _ in 0..10 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the Entity fell down without any Amethyst involvement
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        assert!(positions.get(entity).unwrap().0.translation.vector.y < 5.0);
+    }
+
+    #[test]
+    fn register_optional_systems() {
+        // the default Dispatcher leaves out the optional TriggerEventsSystem
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        assert!(!world.has_value::<TriggerEvents>());
+
+        // whereas the Dispatcher with all Systems sets it up
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        assert!(world.has_value::<TriggerEvents>());
+    }
+
+    #[test]
+    fn physics_dispatcher_events() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // the event channels exist right after setting up the Dispatcher, so
+        // readers can be registered before the first dispatch
+        assert!(world.has_value::<ContactEvents>());
+        assert!(world.has_value::<ProximityEvents>());
+        world.write_resource::<ContactEvents>().register_reader();
+        dispatcher.dispatch(&world);
+    }
+}
@@ -2,7 +2,10 @@
 //! Resources for modifying the various simulation parameters of the
 //! nphysics World.
 
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    time::Duration,
+};
 
 use crate::{
     nalgebra::{self as na, RealField, Scalar, Vector3},
@@ -41,6 +44,83 @@ impl<N: RealField> Default for TimeStep<N> {
     }
 }
 
+/// The `EnergyAdaptiveStep` picks the `TimeStep` from an estimate of the total
+/// kinetic energy of the simulation, so impacts are simulated with smaller
+/// steps while calm scenes are stepped with larger ones. While this
+/// `Resource` exists, the `EnergyAdaptiveStepSystem` writes the `TimeStep`
+/// picked for `Physics::kinetic_energy` before every step.
+///
+/// Every configured step size is used from its energy threshold on; if the
+/// energy is below all thresholds, `max_step` is used. The picked step is
+/// always clamped between `min_step` and `max_step`. Keep the number of step
+/// sizes low, as every change of the `TimeStep` slightly destabilizes the
+/// simulation.
+///
+/// # Example
+///
+/// ```rust
+/// use specs_physics::parameters::EnergyAdaptiveStep;
+///
+/// let adaptive_step = EnergyAdaptiveStep::<f32>::new(1.0 / 240.0, 1.0 / 60.0)
+///     .with_step(100.0, 1.0 / 120.0)
+///     .with_step(1000.0, 1.0 / 240.0);
+///
+/// assert_eq!(adaptive_step.step(10.0), 1.0 / 60.0);
+/// assert_eq!(adaptive_step.step(500.0), 1.0 / 120.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnergyAdaptiveStep<N: RealField> {
+    /// The smallest step size that is ever picked.
+    pub min_step: N,
+    /// The largest step size that is ever picked.
+    pub max_step: N,
+    // pairs of energy thresholds and step sizes, sorted by ascending threshold
+    steps: Vec<(N, N)>,
+}
+
+impl<N: RealField> EnergyAdaptiveStep<N> {
+    /// Creates a new `EnergyAdaptiveStep` without any step sizes, which always
+    /// picks `max_step`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_step` is not positive or larger than `max_step`.
+    pub fn new(min_step: N, max_step: N) -> Self {
+        assert!(
+            min_step > N::zero() && min_step <= max_step,
+            "min_step must be positive and must not exceed max_step"
+        );
+        Self {
+            min_step,
+            max_step,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Adds a step size that is picked once the kinetic energy reaches
+    /// `energy`, unless a step size with a higher threshold applies.
+    pub fn with_step(mut self, energy: N, step: N) -> Self {
+        let index = self
+            .steps
+            .iter()
+            .position(|(threshold, _)| *threshold > energy)
+            .unwrap_or_else(|| self.steps.len());
+        self.steps.insert(index, (energy, step));
+        self
+    }
+
+    /// Picks the step size for the given estimate of the kinetic energy.
+    pub fn step(&self, energy: N) -> N {
+        let step = self
+            .steps
+            .iter()
+            .rev()
+            .find(|(threshold, _)| energy >= *threshold)
+            .map_or(self.max_step, |(_, step)| *step);
+        step.max(self.min_step).min(self.max_step)
+    }
+}
+
 /// `Gravity` is a newtype for `Vector3`. It represents a constant
 /// acceleration affecting all physical objects in the scene.
 #[derive(Debug, PartialEq)]
@@ -91,6 +171,138 @@ impl Default for PhysicsProfilingEnabled {
     }
 }
 
+/// Pauses the simulation while set to `true`. The `PhysicsStepperSystem` does
+/// not progress the nphysics World while paused, but all synchronisation
+/// `System`s keep running. Resuming continues with the next regular timestep.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PhysicsPaused(pub bool);
+
+impl Deref for PhysicsPaused {
+    type Target = bool;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for PhysicsPaused {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Default for PhysicsPaused {
+    fn default() -> Self {
+        Self(false)
+    }
+}
+
+/// Advances a paused simulation by exactly one step when set to `true`. The
+/// `PhysicsStepperSystem` resets the flag after stepping, so the simulation
+/// stays paused afterwards. Has no effect while the simulation isn't paused.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StepOnce(pub bool);
+
+impl Deref for StepOnce {
+    type Target = bool;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for StepOnce {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Default for StepOnce {
+    fn default() -> Self {
+        Self(false)
+    }
+}
+
+/// Returns whether the `PhysicsStepperSystem` progresses the simulation in the
+/// current dispatch, according to the `PhysicsPaused` and `StepOnce`
+/// `Resource`s. `System`s applying forces before the step use this to skip
+/// paused dispatches, as nphysics only clears applied forces when stepping.
+pub(crate) fn is_stepping(paused: Option<&PhysicsPaused>, step_once: Option<&StepOnce>) -> bool {
+    !paused.map_or(false, |paused| paused.0) || step_once.map_or(false, |step_once| step_once.0)
+}
+
+/// Controls whether the synchronisation `System`s dump the full values of
+/// inserted and updated `PhysicsBody`, `PhysicsCollider` and `PhysicsJoint`
+/// `Component`s. The dumps are logged at the `trace` level and disabled by
+/// default.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PhysicsLogVerbosity {
+    /// Only the ids of synchronised `Component`s are logged.
+    Quiet,
+    /// The full values of synchronised `Component`s are logged as well.
+    Verbose,
+}
+
+impl Default for PhysicsLogVerbosity {
+    fn default() -> Self {
+        PhysicsLogVerbosity::Quiet
+    }
+}
+
+/// Enables hashing the state of all bodies after every step into the
+/// `StepHash` `Resource`. Meant for debugging lockstep multiplayer, where
+/// comparing the `StepHash` of two clients reveals a diverging simulation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StepHashEnabled(pub bool);
+
+impl Deref for StepHashEnabled {
+    type Target = bool;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for StepHashEnabled {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Default for StepHashEnabled {
+    fn default() -> Self {
+        Self(false)
+    }
+}
+
+/// The hash of the positions and velocities of all `RigidBody`s after the
+/// last step, written by the `PhysicsStepperSystem` while `StepHashEnabled` is
+/// set. Bodies are hashed in the order of their `Entity` indices with the
+/// 64 bit FNV-1a hash over the little-endian bytes of their ids and of the bit
+/// patterns of their values. The hash is thus stable across platforms and Rust
+/// releases: two machines report the same hash exactly if their simulations
+/// produced bit-identical results.
+///
+/// The physics `System`s iterate `Entity`s in the order of their indices and
+/// sort internal lookup tables before order-dependent operations, so
+/// identically built worlds are simulated identically.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct StepHash(pub u64);
+
+/// Wall-clock timings of the `PhysicsStepperSystem`, measured with `Instant`
+/// independently of the nphysics performance counters. A frame is a single
+/// run of the `PhysicsStepperSystem`; the frame values are reset at the
+/// beginning of every run, so a paused simulation reports no steps.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct PhysicsTiming {
+    /// The duration of the last nphysics step.
+    pub last_step: Duration,
+    /// The number of steps performed in the current frame.
+    pub steps_this_frame: u32,
+    /// The total duration of all steps performed in the current frame.
+    pub time_this_frame: Duration,
+}
+
 /// Essentially identical to the nphysics IntegrationParameters struct except
 /// without the t and dt fields. Manages the details of physics integration.
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -154,6 +366,12 @@ pub struct PhysicsIntegrationParameters<N: RealField> {
     ///
     /// default: `3`
     pub max_position_iterations: usize,
+
+    /// Maximum number of substeps performed by the continuous collision
+    /// detection solver. Must be at least `1`.
+    ///
+    /// default: `1`
+    pub max_ccd_substeps: usize,
 }
 
 impl<N: RealField> PhysicsIntegrationParameters<N> {
@@ -168,6 +386,7 @@ impl<N: RealField> PhysicsIntegrationParameters<N> {
         to.max_stabilization_multiplier = self.max_stabilization_multiplier;
         to.max_velocity_iterations = self.max_velocity_iterations;
         to.max_position_iterations = self.max_position_iterations;
+        to.max_ccd_substeps = self.max_ccd_substeps;
     }
 }
 
@@ -183,6 +402,7 @@ impl<N: RealField> PartialEq<IntegrationParameters<N>> for PhysicsIntegrationPar
             && self.max_stabilization_multiplier == other.max_stabilization_multiplier
             && self.max_velocity_iterations == other.max_velocity_iterations
             && self.max_position_iterations == other.max_position_iterations
+            && self.max_ccd_substeps == other.max_ccd_substeps
     }
 }
 
@@ -199,6 +419,7 @@ impl<N: RealField> Default for PhysicsIntegrationParameters<N> {
             max_stabilization_multiplier: na::convert(0.2),
             max_velocity_iterations: 8,
             max_position_iterations: 3,
+            max_ccd_substeps: 1,
         }
     }
 }
@@ -0,0 +1,69 @@
+//! Re-exports of the commonly used types of **specs-physics**, so getting
+//! started only takes a single `use specs_physics::prelude::*;`.
+
+#[cfg(feature = "amethyst")]
+pub use crate::amethyst::PhysicsBundle;
+pub use crate::{
+    all_physics_dispatcher,
+    bodies::{Position, PositionSyncDisabled, SimplePosition},
+    colliders::Shape,
+    events::{BodySleepEvents, ContactEvents, JointBrokenEvents, ProximityEvents, TriggerEvents},
+    joints::{JointType, ReadJoints, WriteJoints},
+    layers::{CollisionMatrix, Layer},
+    nalgebra::{Isometry3, Point3, Vector3},
+    nphysics::{algebra::Velocity3, object::BodyStatus},
+    parameters::{Gravity, PhysicsPaused, StepOnce, TimeStep},
+    physics_dispatcher,
+    register_all_physics_systems,
+    register_physics_systems,
+    EntityBuilderExt,
+    Physics,
+    PhysicsBody,
+    PhysicsBodyBuilder,
+    PhysicsCollider,
+    PhysicsColliderBuilder,
+    PhysicsJoint,
+    PhysicsJointBuilder,
+};
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn basic_scene() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a ball falling onto static ground using only prelude imports
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with_ground::<f32>()
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 1.0, 10.0),
+                })
+                .build(),
+            )
+            .build();
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 5.0, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        assert!(positions.get(ball).unwrap().0.translation.vector.y < 5.0);
+    }
+}
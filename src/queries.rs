@@ -0,0 +1,1060 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet, VecDeque},
+};
+
+use specs::{
+    world::{EntitiesRes, Index},
+    Entity,
+};
+
+use crate::{
+    colliders::DebugShape,
+    nalgebra::{self as na, Isometry3, Point3, RealField, Translation3, Vector3},
+    ncollide::{
+        bounding_volume::{BoundingVolume, AABB},
+        pipeline::CollisionGroups,
+        query::{self, Contact, PointQuery, Proximity, Ray, RayIntersection, TOI},
+        shape::ShapeHandle,
+    },
+    nphysics::{
+        joint::JointConstraint,
+        object::{Body, BodyPart, Collider, DefaultBodyHandle, DefaultBodySet},
+    },
+    Physics,
+};
+
+// Non-mutating queries of the physics world, e.g. for gameplay and AI
+impl<N: RealField> Physics<N> {
+    /// Retrieves the world position of the body part with the given index of
+    /// the `PhysicsBody` of an `Entity`. `RigidBody`s consist of a single part
+    /// with the index `0`.
+    pub fn part_isometry(&self, entity: Entity, part: usize) -> Option<Isometry3<N>> {
+        let handle = self.body_handles.get(&entity.id())?;
+        let body_part = self.bodies.get(*handle)?.part(part)?;
+
+        Some(body_part.position())
+    }
+
+    /// Retrieves the world position of the `PhysicsCollider` of an `Entity`,
+    /// including its `offset_from_parent`.
+    pub fn collider_isometry(&self, entity: Entity) -> Option<&Isometry3<N>> {
+        let handle = self.collider_handles.get(&entity.id())?;
+
+        Some(self.colliders.get(*handle)?.position())
+    }
+
+    /// Retrieves the `Entity` of the `PhysicsBody` the `PhysicsCollider` of an
+    /// `Entity` is attached to. This is either the `Entity` itself or its
+    /// `PhysicsParent`; colliders attached to the ground have no parent.
+    pub fn collider_parent(&self, entities: &EntitiesRes, entity: Entity) -> Option<Entity> {
+        let handle = self.collider_handles.get(&entity.id())?;
+        let body = self.colliders.get(*handle)?.body();
+
+        body_entity(entities, &self.bodies, body)
+    }
+
+    /// Collects the `Entity`s of all `PhysicsBody`s transitively connected to
+    /// the `PhysicsBody` of the given `Entity` by joints, including the
+    /// `Entity` itself. Joints attached to the ground are not followed, as
+    /// otherwise all bodies jointed to the ground would be connected to each
+    /// other; neither are broken joints. Returns an empty set if the `Entity`
+    /// has no `PhysicsBody`.
+    pub fn connected_bodies(&self, entities: &EntitiesRes, start: Entity) -> HashSet<Entity> {
+        let mut connected = HashSet::new();
+        let start_handle = match self.body_handles.get(&start.id()) {
+            Some(handle) => *handle,
+            None => return connected,
+        };
+
+        // the joints are the edges between the bodies of the graph
+        let mut edges: HashMap<DefaultBodyHandle, Vec<DefaultBodyHandle>> = HashMap::new();
+        for (_, joint_constraint) in self.joint_constraints.iter() {
+            if joint_constraint.is_broken() {
+                continue;
+            }
+            let (anchor1, anchor2) = joint_constraint.anchors();
+            if anchor1.0 == self.ground || anchor2.0 == self.ground {
+                continue;
+            }
+            edges.entry(anchor1.0).or_default().push(anchor2.0);
+            edges.entry(anchor2.0).or_default().push(anchor1.0);
+        }
+
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(start_handle);
+        queue.push_back(start_handle);
+        while let Some(handle) = queue.pop_front() {
+            if let Some(entity) = body_entity(entities, &self.bodies, handle) {
+                connected.insert(entity);
+            }
+            for neighbour in edges.get(&handle).into_iter().flatten() {
+                if visited.insert(*neighbour) {
+                    queue.push_back(*neighbour);
+                }
+            }
+        }
+
+        connected
+    }
+
+    /// Retrieves the deepest contact point between the `PhysicsCollider`s of
+    /// the given `Entity`s as computed during the last simulated timestep.
+    /// Contact manifolds may contain several points, only the deepest one is
+    /// returned; its normal points from `entity1` towards `entity2`.
+    pub fn deepest_contact(&self, entity1: Entity, entity2: Entity) -> Option<Contact<N>> {
+        let handle1 = *self.collider_handles.get(&entity1.id())?;
+        let handle2 = *self.collider_handles.get(&entity2.id())?;
+        let (first_handle, _, _, _, _, manifold) =
+            self.geometrical_world
+                .contact_pair(&self.colliders, handle1, handle2, true)?;
+
+        // the contact is reported relative to the first collider of the pair, which
+        // is not necessarily the collider of entity1
+        let mut contact = manifold.deepest_contact()?.contact;
+        if first_handle != handle1 {
+            contact.flip();
+        }
+
+        Some(contact)
+    }
+
+    /// Iterates over all `Entity`s whose `PhysicsCollider`s are currently in
+    /// contact with the `PhysicsCollider` of the given `Entity`. Sensors never
+    /// generate contacts; set `include_sensors` to also yield sensors that
+    /// intersect the `Entity`s collider, or the colliders intersecting it if it
+    /// is a sensor itself.
+    pub fn contacts_with<'a>(
+        &'a self,
+        entities: &'a EntitiesRes,
+        entity: Entity,
+        include_sensors: bool,
+    ) -> impl Iterator<Item = Entity> + 'a {
+        let handle = self.collider_handles.get(&entity.id()).cloned();
+        let contacts = handle
+            .and_then(move |handle| {
+                self.geometrical_world
+                    .colliders_in_contact_with(&self.colliders, handle)
+            })
+            .into_iter()
+            .flatten();
+        let proximities = handle
+            .filter(|_| include_sensors)
+            .and_then(move |handle| {
+                self.geometrical_world
+                    .colliders_in_proximity_of(&self.colliders, handle)
+            })
+            .into_iter()
+            .flatten();
+
+        // map the colliders back to their Entities via their user data
+        contacts
+            .chain(proximities)
+            .filter_map(move |(_, collider)| {
+                collider
+                    .user_data()
+                    .and_then(|user_data| user_data.downcast_ref::<Index>())
+                    .map(|id| entities.entity(*id))
+            })
+    }
+
+    /// Describes the shapes of all `PhysicsCollider`s together with their
+    /// `Entity` and world position, e.g. for drawing wireframes in a debug
+    /// renderer. `Shape::Compound`s are expanded into their parts.
+    pub fn debug_shapes(
+        &self,
+        entities: &EntitiesRes,
+    ) -> impl Iterator<Item = (Entity, Isometry3<N>, DebugShape<N>)> {
+        let mut debug_shapes = Vec::new();
+        for (_, collider) in self.colliders.iter() {
+            let entity = match collider
+                .user_data()
+                .and_then(|user_data| user_data.downcast_ref::<Index>())
+            {
+                Some(id) => entities.entity(*id),
+                None => continue,
+            };
+
+            let mut shapes = Vec::new();
+            DebugShape::describe(collider.position(), collider.shape_handle(), &mut shapes);
+            debug_shapes.extend(
+                shapes
+                    .into_iter()
+                    .map(|(position, debug_shape)| (entity, position, debug_shape)),
+            );
+        }
+
+        debug_shapes.into_iter()
+    }
+
+    /// Sweeps the given shape from `from` along `direction` and returns the
+    /// first `PhysicsCollider` it hits together with its `Entity`. The
+    /// time of impact is expressed in multiples of `direction`, sweeps are
+    /// stopped at `max_toi`. Sensors, colliders outside of `groups` and the
+    /// colliders of the `exclude`d `Entity`s, such as the caster itself, are
+    /// ignored.
+    pub fn shape_cast(
+        &self,
+        entities: &EntitiesRes,
+        shape: &ShapeHandle<N>,
+        from: &Isometry3<N>,
+        direction: &Vector3<N>,
+        max_toi: N,
+        groups: &CollisionGroups,
+        exclude: &[Entity],
+    ) -> Option<(Entity, TOI<N>)> {
+        // only colliders touching the AABB covering the whole sweep can be hit
+        let to = Translation3::from(direction * max_toi) * from;
+        let swept_aabb = shape.aabb(from).merged(&shape.aabb(&to));
+
+        self.geometrical_world
+            .interferences_with_aabb(&self.colliders, &swept_aabb, groups)
+            .filter(|(_, collider)| !collider.is_sensor())
+            .filter_map(|(_, collider)| {
+                let entity = collider_entity(entities, collider)?;
+                if exclude.contains(&entity) {
+                    return None;
+                }
+
+                let toi = query::time_of_impact(
+                    from,
+                    direction,
+                    &**shape,
+                    collider.position(),
+                    &Vector3::zeros(),
+                    collider.shape(),
+                    max_toi,
+                    N::zero(),
+                )?;
+                Some((entity, toi))
+            })
+            .min_by(|(_, toi1), (_, toi2)| {
+                toi1.toi.partial_cmp(&toi2.toi).unwrap_or(Ordering::Equal)
+            })
+    }
+
+    /// Checks which `PhysicsCollider`s overlap the given shape at `pose`
+    /// without inserting it into the physics world, e.g. to check whether a
+    /// spawn location is free. Returns the overlapping `Entity`s in ascending
+    /// order. Sensors and colliders outside of `groups` are ignored.
+    pub fn test_overlap(
+        &self,
+        entities: &EntitiesRes,
+        shape: &ShapeHandle<N>,
+        pose: &Isometry3<N>,
+        groups: &CollisionGroups,
+    ) -> Vec<Entity> {
+        let mut overlapping = self
+            .geometrical_world
+            .interferences_with_aabb(&self.colliders, &shape.aabb(pose), groups)
+            .filter(|(_, collider)| !collider.is_sensor())
+            .filter(|(_, collider)| {
+                query::proximity(
+                    pose,
+                    &**shape,
+                    collider.position(),
+                    collider.shape(),
+                    N::zero(),
+                ) == Proximity::Intersecting
+            })
+            .filter_map(|(_, collider)| collider_entity(entities, collider))
+            .collect::<Vec<_>>();
+        overlapping.sort();
+
+        overlapping
+    }
+
+    /// Casts the given ray and returns all `PhysicsCollider`s it hits within
+    /// `max_toi`, together with their `Entity`, sorted by increasing time of
+    /// impact. Colliders outside of `groups` are ignored, as are sensors unless
+    /// `include_sensors` is set.
+    pub fn raycast_all(
+        &self,
+        entities: &EntitiesRes,
+        ray: &Ray<N>,
+        max_toi: N,
+        groups: &CollisionGroups,
+        include_sensors: bool,
+    ) -> Vec<(Entity, RayIntersection<N>)> {
+        let mut hits = self
+            .geometrical_world
+            .interferences_with_ray(&self.colliders, ray, max_toi, groups)
+            .filter(|(_, collider, _)| include_sensors || !collider.is_sensor())
+            .filter_map(|(_, collider, intersection)| {
+                Some((collider_entity(entities, collider)?, intersection))
+            })
+            .collect::<Vec<_>>();
+        hits.sort_by(|(_, intersection1), (_, intersection2)| {
+            intersection1
+                .toi
+                .partial_cmp(&intersection2.toi)
+                .unwrap_or(Ordering::Equal)
+        });
+
+        hits
+    }
+
+    /// Returns up to `n` `PhysicsCollider`s within `max_distance` of `point`
+    /// together with their `Entity` and distance, sorted by increasing
+    /// distance, e.g. for AI target selection. Points inside a collider are at
+    /// distance zero. Sensors and colliders outside of `groups` are ignored.
+    ///
+    /// The broad phase is queried with a search box growing up to
+    /// `max_distance`, so only the colliders near `point` are measured.
+    /// Unbounded shapes such as `Plane`s overlap every search box; they are
+    /// measured on every query and returned like any other collider if they
+    /// are close enough.
+    ///
+    /// Fewer than `n` results are only returned if fewer colliders exist within
+    /// `max_distance`. Colliders at equal distances are ordered by their
+    /// `Entity`, so if more colliders tie with the `n`th one, those with the
+    /// lower `Entity` ids are returned.
+    pub fn nearest_colliders(
+        &self,
+        entities: &EntitiesRes,
+        point: &Point3<N>,
+        n: usize,
+        max_distance: N,
+        groups: &CollisionGroups,
+    ) -> Vec<(Entity, N)> {
+        if n == 0 || max_distance < N::zero() {
+            return Vec::new();
+        }
+
+        // grow the search box until it contains n colliders within its half
+        // extents or reaches the max_distance; colliders outside of the box are
+        // further away than that
+        let mut radius = N::one().min(max_distance);
+        loop {
+            let search = AABB::from_half_extents(*point, Vector3::repeat(radius));
+            let mut nearest = self
+                .geometrical_world
+                .interferences_with_aabb(&self.colliders, &search, groups)
+                .filter(|(_, collider)| !collider.is_sensor())
+                .filter_map(|(_, collider)| {
+                    let distance =
+                        collider
+                            .shape()
+                            .distance_to_point(collider.position(), point, true);
+                    if distance > max_distance {
+                        return None;
+                    }
+                    Some((collider_entity(entities, collider)?, distance))
+                })
+                .collect::<Vec<_>>();
+            nearest.sort_by(|(entity1, distance1), (entity2, distance2)| {
+                distance1
+                    .partial_cmp(distance2)
+                    .unwrap_or(Ordering::Equal)
+                    .then(entity1.cmp(entity2))
+            });
+
+            if radius >= max_distance || (nearest.len() >= n && nearest[n - 1].1 <= radius) {
+                nearest.truncate(n);
+                return nearest;
+            }
+            radius = (radius * na::convert(2.0)).min(max_distance);
+        }
+    }
+
+    /// Checks whether the straight line between `from` and `to` is free of
+    /// solid `PhysicsCollider`s, e.g. for AI perception. Sensors, colliders
+    /// outside of `groups` and the colliders of the `exclude`d `Entity`s, such
+    /// as the observer and its target, don't block the line of sight.
+    pub fn line_of_sight(
+        &self,
+        entities: &EntitiesRes,
+        from: &Point3<N>,
+        to: &Point3<N>,
+        groups: &CollisionGroups,
+        exclude: &[Entity],
+    ) -> bool {
+        // the direction spans the whole segment, so it ends at a time of impact of 1
+        let ray = Ray::new(*from, to - from);
+        self.raycast_all(entities, &ray, N::one(), groups, false)
+            .iter()
+            .all(|(entity, _)| exclude.contains(entity))
+    }
+
+    /// Casts a ray straight down from `origin` and returns the closest solid
+    /// `PhysicsCollider` within `max_distance`, e.g. to check whether a
+    /// character is grounded. Sensors, colliders outside of `groups` and the
+    /// colliders of the `exclude`d `Entity`s, usually the caster itself, are
+    /// ignored. Down is the negative y axis.
+    pub fn ground_check(
+        &self,
+        entities: &EntitiesRes,
+        origin: &Point3<N>,
+        max_distance: N,
+        groups: &CollisionGroups,
+        exclude: &[Entity],
+    ) -> Option<GroundInfo<N>> {
+        let ray = Ray::new(*origin, -Vector3::y());
+        self.raycast_all(entities, &ray, max_distance, groups, false)
+            .into_iter()
+            .find(|(entity, _)| !exclude.contains(entity))
+            .map(|(entity, intersection)| GroundInfo {
+                entity,
+                distance: intersection.toi,
+                normal: intersection.normal,
+            })
+    }
+}
+
+/// The `GroundInfo` describes the ground found by `Physics::ground_check`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GroundInfo<N: RealField> {
+    /// The `Entity` of the ground `PhysicsCollider`.
+    pub entity: Entity,
+    /// The distance between the origin of the check and the ground.
+    pub distance: N,
+    /// The normal of the ground surface at the hit point.
+    pub normal: Vector3<N>,
+}
+
+/// Maps the given `Collider` back to the `Entity` of its `PhysicsCollider`
+/// via its user data.
+fn collider_entity<N: RealField>(
+    entities: &EntitiesRes,
+    collider: &Collider<N, DefaultBodyHandle>,
+) -> Option<Entity> {
+    collider
+        .user_data()
+        .and_then(|user_data| user_data.downcast_ref::<Index>())
+        .map(|id| entities.entity(*id))
+}
+
+fn body_entity<N: RealField>(
+    entities: &EntitiesRes,
+    bodies: &DefaultBodySet<N>,
+    handle: DefaultBodyHandle,
+) -> Option<Entity> {
+    bodies
+        .rigid_body(handle)?
+        .user_data()
+        .and_then(|user_data| user_data.downcast_ref::<Index>())
+        .map(|id| entities.entity(*id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use specs::prelude::*;
+
+    use crate::{
+        colliders::{DebugShape, Shape},
+        joints::{BallJoint, JointType},
+        nalgebra::{Isometry3, Point3, Vector3},
+        ncollide::{
+            pipeline::CollisionGroups,
+            query::Ray,
+            shape::{Ball, Capsule, ShapeHandle},
+        },
+        nphysics::object::BodyStatus,
+        physics_dispatcher,
+        EntityBuilderExt,
+        Physics,
+        PhysicsBody,
+        PhysicsBodyBuilder,
+        PhysicsColliderBuilder,
+        PhysicsJointBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn deepest_contact() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create two overlapping cuboids and execute the dispatcher
+        let mut create_cuboid = |x| {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(x, 0.0, 0.0)))
+                .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+                .with(
+                    PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                        half_extents: Vector3::new(1.0, 1.0, 1.0),
+                    })
+                    .build(),
+                )
+                .build()
+        };
+        let entity1 = create_cuboid(0.0);
+        let entity2 = create_cuboid(1.5);
+        dispatcher.dispatch(&world);
+
+        // the contact normal points from the first towards the second cuboid
+        let physics = world.read_resource::<Physics<f32>>();
+        let contact = physics.deepest_contact(entity1, entity2).unwrap();
+        assert!(contact.normal.x > 0.9);
+        assert!(contact.depth > 0.0);
+
+        // and the other way around when swapping both Entities
+        let contact = physics.deepest_contact(entity2, entity1).unwrap();
+        assert!(contact.normal.x < -0.9);
+    }
+
+    #[test]
+    fn contacts_with() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a static ground collider with a ball resting on top of it
+        let ground = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 1.0, 10.0),
+                })
+                .build(),
+            )
+            .build();
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 1.9, 0.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        // the ground shows up in the contacts of the ball
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let contacts = physics
+            .contacts_with(&entities, ball, false)
+            .collect::<Vec<_>>();
+        assert_eq!(contacts, vec![ground]);
+    }
+
+    #[test]
+    fn debug_shapes() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a ball and a cuboid collider
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(1.0, 0.0, 0.0)))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+        let cuboid = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                -1.0, 0.0, 0.0,
+            )))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(1.0, 2.0, 3.0),
+                })
+                .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+
+        // both colliders are described with their dimensions and positions
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let mut debug_shapes = physics.debug_shapes(&entities).collect::<Vec<_>>();
+        debug_shapes.sort_by_key(|(entity, ..)| *entity);
+        assert_eq!(
+            debug_shapes,
+            vec![
+                (
+                    ball,
+                    Isometry3::translation(1.0, 0.0, 0.0),
+                    DebugShape::Ball { radius: 0.5 }
+                ),
+                (
+                    cuboid,
+                    Isometry3::translation(-1.0, 0.0, 0.0),
+                    DebugShape::Cuboid {
+                        half_extents: Vector3::new(1.0, 2.0, 3.0)
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn part_isometry() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with a PhysicsBody
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(1.0, 2.0, 3.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        // a RigidBody consists of a single part only
+        let physics = world.read_resource::<Physics<f32>>();
+        assert_eq!(
+            physics.part_isometry(entity, 0),
+            Some(Isometry3::translation(1.0, 2.0, 3.0))
+        );
+        assert_eq!(physics.part_isometry(entity, 1), None);
+    }
+
+    #[test]
+    fn collider_accessors() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a body with a collider attached at an offset and a ground collider
+        let body = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(1.0, 2.0, 3.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .build();
+        let collider = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .attach_collider(
+                body,
+                PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 })
+                    .offset_from_parent(Isometry3::translation(0.0, 1.0, 0.0))
+                    .build(),
+            )
+            .build();
+        let ground = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                0.0, -5.0, 0.0,
+            )))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        assert_eq!(
+            physics.collider_isometry(collider),
+            Some(&Isometry3::translation(1.0, 3.0, 3.0))
+        );
+        assert_eq!(physics.collider_parent(&entities, collider), Some(body));
+        assert_eq!(
+            physics.collider_isometry(ground),
+            Some(&Isometry3::translation(0.0, -5.0, 0.0))
+        );
+        assert_eq!(physics.collider_parent(&entities, ground), None);
+    }
+
+    #[test]
+    fn shape_cast() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a wall with its surface at x = 4.5 and execute the dispatcher
+        let wall = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(5.0, 0.0, 0.0)))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(0.5, 5.0, 5.0),
+                })
+                .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+
+        // sweep a ball towards the wall; it hits once the gap is closed
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let ball = ShapeHandle::new(Ball::new(0.5));
+        let (entity, toi) = physics
+            .shape_cast(
+                &entities,
+                &ball,
+                &Isometry3::identity(),
+                &Vector3::x(),
+                10.0,
+                &CollisionGroups::default(),
+                &[],
+            )
+            .unwrap();
+        assert_eq!(entity, wall);
+        assert!((toi.toi - 4.0).abs() < 1.0e-3);
+
+        // unless the wall is excluded
+        assert!(physics
+            .shape_cast(
+                &entities,
+                &ball,
+                &Isometry3::identity(),
+                &Vector3::x(),
+                10.0,
+                &CollisionGroups::default(),
+                &[wall],
+            )
+            .is_none());
+
+        // sweeping away from the wall hits nothing
+        assert!(physics
+            .shape_cast(
+                &entities,
+                &ball,
+                &Isometry3::identity(),
+                &-Vector3::x(),
+                10.0,
+                &CollisionGroups::default(),
+                &[],
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn raycast_all() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create three aligned balls, the middle one being a sensor
+        let mut create_ball = |x, sensor| {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(x, 0.0, 0.0)))
+                .with(
+                    PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 })
+                        .sensor(sensor)
+                        .build(),
+                )
+                .build()
+        };
+        let far = create_ball(6.0, false);
+        let near = create_ball(2.0, false);
+        let sensor = create_ball(4.0, true);
+        dispatcher.dispatch(&world);
+
+        // all three balls are hit in order of their distance
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let ray = Ray::new(Point3::origin(), Vector3::x());
+        let hits = physics.raycast_all(&entities, &ray, 10.0, &CollisionGroups::default(), true);
+        assert_eq!(
+            hits.iter().map(|(entity, _)| *entity).collect::<Vec<_>>(),
+            vec![near, sensor, far]
+        );
+        assert!((hits[0].1.toi - 1.5).abs() < 1.0e-5);
+
+        // the sensor is skipped unless explicitly included
+        let hits = physics.raycast_all(&entities, &ray, 10.0, &CollisionGroups::default(), false);
+        assert_eq!(
+            hits.iter().map(|(entity, _)| *entity).collect::<Vec<_>>(),
+            vec![near, far]
+        );
+    }
+
+    #[test]
+    fn line_of_sight() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a wall between both points as well as the observer
+        let wall = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(0.5, 5.0, 5.0),
+                })
+                .build(),
+            )
+            .build();
+        let observer = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                -3.0, 0.0, 0.0,
+            )))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        // the wall blocks the line of sight, the excluded observer does not
+        let from = Point3::new(-3.0, 0.0, 0.0);
+        let to = Point3::new(3.0, 0.0, 0.0);
+        {
+            let physics = world.read_resource::<Physics<f32>>();
+            let entities = world.entities();
+            let groups = CollisionGroups::default();
+            assert!(!physics.line_of_sight(&entities, &from, &to, &groups, &[observer]));
+        }
+
+        // without the wall the target is visible
+        world.delete_entity(wall).unwrap();
+        world.maintain();
+        dispatcher.dispatch(&world);
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let groups = CollisionGroups::default();
+        assert!(physics.line_of_sight(&entities, &from, &to, &groups, &[observer]));
+        assert!(!physics.line_of_sight(&entities, &from, &to, &groups, &[]));
+    }
+
+    #[test]
+    fn ground_check() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create flat ground and a character standing on top of it
+        let ground = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                0.0, -1.0, 0.0,
+            )))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 1.0, 10.0),
+                })
+                .build(),
+            )
+            .build();
+        let character = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 0.5, 0.0)))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        // checking from the feet of the character finds the ground right below
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let ground_info = physics
+            .ground_check(
+                &entities,
+                &Point3::new(0.0, 0.01, 0.0),
+                0.1,
+                &CollisionGroups::default(),
+                &[character],
+            )
+            .unwrap();
+        assert_eq!(ground_info.entity, ground);
+        assert!(ground_info.distance < 0.02);
+        assert!((ground_info.normal - Vector3::y()).norm() < 1.0e-5);
+
+        // nothing is found above the ground
+        assert!(physics
+            .ground_check(
+                &entities,
+                &Point3::new(0.0, 5.0, 0.0),
+                0.1,
+                &CollisionGroups::default(),
+                &[character],
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn connected_bodies() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a chain of three bodies, the last one hanging from the ground,
+        // and a separate body hanging from the ground as well
+        let ball_joint = || {
+            PhysicsJointBuilder::<f32>::from(JointType::Ball(BallJoint {
+                local_anchor_1: Point3::new(0.0, 0.5, 0.0),
+                local_anchor_2: Point3::new(0.0, -0.5, 0.0),
+            }))
+        };
+        let mut create_body = |y: f32, joint: Option<PhysicsJointBuilder<f32>>| {
+            let builder = world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(0.0, y, 0.0)))
+                .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build());
+            match joint {
+                Some(joint) => builder.with_joint(joint.build()).build(),
+                None => builder.build(),
+            }
+        };
+        let top = create_body(3.0, Some(ball_joint()));
+        let middle = create_body(2.0, Some(ball_joint().connected_entity(top)));
+        let bottom = create_body(1.0, Some(ball_joint().connected_entity(middle)));
+        let separate = create_body(-5.0, Some(ball_joint()));
+        dispatcher.dispatch(&world);
+
+        // all bodies of the chain are found from either end, but the ground isn't
+        // traversed into the separate body
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let chain = [top, middle, bottom]
+            .iter()
+            .cloned()
+            .collect::<HashSet<_>>();
+        assert_eq!(physics.connected_bodies(&entities, bottom), chain);
+        assert_eq!(physics.connected_bodies(&entities, top), chain);
+        assert_eq!(
+            physics.connected_bodies(&entities, separate),
+            [separate].iter().cloned().collect::<HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn test_overlap() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create two static colliders next to each other
+        let mut create_ball = |x| {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(x, 0.0, 0.0)))
+                .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+                .build()
+        };
+        let left = create_ball(-2.0);
+        create_ball(2.0);
+        dispatcher.dispatch(&world);
+
+        // a capsule placed next to the left collider only overlaps that one
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let capsule = ShapeHandle::new(Capsule::new(1.0, 0.5));
+        let overlapping = physics.test_overlap(
+            &entities,
+            &capsule,
+            &Isometry3::translation(-0.8, 0.0, 0.0),
+            &CollisionGroups::new(),
+        );
+        assert_eq!(overlapping, vec![left]);
+
+        // whereas the space between both colliders is free
+        let overlapping = physics.test_overlap(
+            &entities,
+            &capsule,
+            &Isometry3::translation(0.0, 2.0, 0.0),
+            &CollisionGroups::new(),
+        );
+        assert!(overlapping.is_empty());
+    }
+
+    #[test]
+    fn nearest_colliders() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // scatter a few static colliders at increasing distances from the origin
+        let mut create_ball = |x: f32, z: f32| {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(x, 0.0, z)))
+                .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+                .build()
+        };
+        let far = create_ball(-20.0, 0.0);
+        let near = create_ball(2.0, 0.0);
+        let farther = create_ball(0.0, -7.5);
+        let nearer = create_ball(0.0, 1.0);
+        dispatcher.dispatch(&world);
+
+        // the closest three are returned in the order of their distance
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let nearest = physics.nearest_colliders(
+            &entities,
+            &Point3::origin(),
+            3,
+            100.0,
+            &CollisionGroups::new(),
+        );
+        assert_eq!(
+            nearest
+                .iter()
+                .map(|(entity, _)| *entity)
+                .collect::<Vec<_>>(),
+            vec![nearer, near, farther]
+        );
+        assert!((nearest[0].1 - 0.5).abs() < 1.0e-5);
+        assert!((nearest[2].1 - 7.0).abs() < 1.0e-5);
+
+        // asking for more colliders than exist returns all of them
+        let nearest = physics.nearest_colliders(
+            &entities,
+            &Point3::origin(),
+            10,
+            100.0,
+            &CollisionGroups::new(),
+        );
+        assert_eq!(nearest.len(), 4);
+        assert_eq!(nearest[3].0, far);
+
+        // colliders beyond the max_distance are left out
+        let nearest = physics.nearest_colliders(
+            &entities,
+            &Point3::origin(),
+            10,
+            5.0,
+            &CollisionGroups::new(),
+        );
+        assert_eq!(
+            nearest
+                .iter()
+                .map(|(entity, _)| *entity)
+                .collect::<Vec<_>>(),
+            vec![nearer, near]
+        );
+    }
+
+    #[test]
+    fn nearest_colliders_with_plane() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an unbounded ground plane below a ball and execute the dispatcher
+        let ground = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                0.0, -3.0, 0.0,
+            )))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Plane {
+                    normal: Vector3::y_axis(),
+                })
+                .build(),
+            )
+            .build();
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(2.0, 0.0, 0.0)))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        // the plane is measured like any other collider and the search stops at
+        // the max_distance despite its infinite bounds
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let nearest = physics.nearest_colliders(
+            &entities,
+            &Point3::origin(),
+            10,
+            10.0,
+            &CollisionGroups::new(),
+        );
+        assert_eq!(
+            nearest
+                .iter()
+                .map(|(entity, _)| *entity)
+                .collect::<Vec<_>>(),
+            vec![ball, ground]
+        );
+        assert!((nearest[1].1 - 3.0).abs() < 1.0e-5);
+    }
+}
@@ -0,0 +1,393 @@
+//! # Ragdoll module
+//! Spawns ragdolls from a hierarchy of bones.
+//!
+//! Every bone of a ragdoll is a dynamic `PhysicsBody` with a capsule
+//! `PhysicsCollider` along its local y axis; child bones hang below the anchor
+//! on their parent bone and are connected to it with a `PhysicsJoint`. All
+//! colliders of a ragdoll share a self-collision group, so adjacent limbs
+//! overlapping at their joints do not push each other apart.
+//!
+//! **Every ragdoll needs its own self-collision group**: ragdolls sharing a
+//! group pass through each other, as their colliders are excluded from
+//! colliding just like the limbs of a single ragdoll.
+
+use specs::{Builder, Entity, World, WorldExt};
+
+use crate::{
+    bodies::Position,
+    colliders::Shape,
+    joints::{BallJoint, JointLimits, JointType, RevoluteJoint},
+    nalgebra::{self as na, Isometry3, Point3, RealField, Unit, Vector3},
+    ncollide::pipeline::CollisionGroups,
+    nphysics::object::BodyStatus,
+    EntityBuilderExt,
+    PhysicsBodyBuilder,
+    PhysicsColliderBuilder,
+    PhysicsJointBuilder,
+};
+
+/// The `BoneJoint` defines how a bone is connected to its parent bone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoneJoint<N: RealField> {
+    /// Allows the bone to rotate freely around its anchor, like a shoulder.
+    Ball,
+    /// Allows the bone to rotate around a single axis, like an elbow. The
+    /// axis is given in the local space of the ragdoll.
+    Revolute { axis: Unit<Vector3<N>> },
+}
+
+/// A `Bone` describes a single segment of a ragdoll.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bone<N: RealField> {
+    /// The index of the parent bone, if any. Bones without a parent are
+    /// placed at the position of the ragdoll.
+    pub parent: Option<usize>,
+    /// The point on the parent bone, relative to its center, this bone hangs
+    /// from.
+    pub anchor: Point3<N>,
+    /// The length of the cylindrical part of the bone's capsule.
+    pub length: N,
+    /// The radius of the bone's capsule.
+    pub radius: N,
+    /// The joint connecting this bone to its parent bone.
+    pub joint: BoneJoint<N>,
+    /// The limits of the joint; only applied to `BoneJoint::Revolute`s.
+    pub limits: Option<JointLimits<N>>,
+}
+
+impl<N: RealField> Bone<N> {
+    /// Creates a new root `Bone` without a parent.
+    pub fn root(length: N, radius: N) -> Self {
+        Self {
+            parent: None,
+            anchor: Point3::origin(),
+            length,
+            radius,
+            joint: BoneJoint::Ball,
+            limits: None,
+        }
+    }
+
+    /// Creates a new `Bone` hanging from the `anchor` of the `parent` bone.
+    pub fn child(
+        parent: usize,
+        anchor: Point3<N>,
+        length: N,
+        radius: N,
+        joint: BoneJoint<N>,
+    ) -> Self {
+        Self {
+            parent: Some(parent),
+            anchor,
+            length,
+            radius,
+            joint,
+            limits: None,
+        }
+    }
+
+    /// Sets the `limits` value of the `Bone`.
+    pub fn limits(mut self, limits: JointLimits<N>) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    // the offset from the center of the bone to its upper end, which is
+    // attached to the anchor on the parent bone
+    fn top(&self) -> Vector3<N> {
+        Vector3::new(
+            N::zero(),
+            self.length / na::convert(2.0) + self.radius,
+            N::zero(),
+        )
+    }
+}
+
+/// The `RagdollBuilder` spawns the `Entity`s of a ragdoll from a hierarchy of
+/// `Bone`s.
+///
+/// # Example
+///
+/// ```rust
+/// use specs::{World, WorldExt};
+/// use specs_physics::{
+///     nalgebra::{Isometry3, Point3, Vector3},
+///     ragdoll::{Bone, BoneJoint, RagdollBuilder},
+///     SimplePosition,
+/// };
+///
+/// let mut world = World::new();
+/// let mut dispatcher = specs_physics::physics_dispatcher::<f32, SimplePosition<f32>>();
+/// dispatcher.setup(&mut world);
+///
+/// let bones = RagdollBuilder::<f32>::new(Isometry3::translation(0.0, 2.0, 0.0), 0)
+///     .bone(Bone::root(0.6, 0.2))
+///     .bone(Bone::child(
+///         0,
+///         Point3::new(0.0, -0.5, 0.0),
+///         0.4,
+///         0.1,
+///         BoneJoint::Revolute {
+///             axis: Vector3::x_axis(),
+///         },
+///     ))
+///     .build(&mut world, SimplePosition::from_isometry);
+/// assert_eq!(bones.len(), 2);
+/// ```
+pub struct RagdollBuilder<N: RealField> {
+    position: Isometry3<N>,
+    bones: Vec<Bone<N>>,
+    density: N,
+    self_collision_group: usize,
+}
+
+impl<N: RealField> RagdollBuilder<N> {
+    /// Creates a new `RagdollBuilder` placing the root bones at the given
+    /// position. The bone colliders are members of the `self_collision_group`
+    /// only and do not collide with each other; use a distinct group for
+    /// every ragdoll, otherwise the ragdolls pass through each other.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self_collision_group` exceeds
+    /// `CollisionGroups::max_group_id()`.
+    pub fn new(position: Isometry3<N>, self_collision_group: usize) -> Self {
+        assert!(
+            self_collision_group <= CollisionGroups::max_group_id(),
+            "The self_collision_group must not exceed CollisionGroups::max_group_id()"
+        );
+
+        Self {
+            position,
+            bones: Vec::new(),
+            density: N::one(),
+            self_collision_group,
+        }
+    }
+
+    /// Adds the given `Bone`; its index is the number of previously added
+    /// bones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parent of the `Bone` has not been added before.
+    pub fn bone(mut self, bone: Bone<N>) -> Self {
+        if let Some(parent) = bone.parent {
+            assert!(
+                parent < self.bones.len(),
+                "The parent of a Bone has to be added before the Bone itself"
+            );
+        }
+
+        self.bones.push(bone);
+        self
+    }
+
+    /// Sets the `density` of the bone colliders of the `RagdollBuilder`.
+    pub fn density(mut self, density: N) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// Spawns an `Entity` for every `Bone` and returns them in the order the
+    /// bones were added. The `Position` of each `Entity` is created from its
+    /// initial `Isometry3` by the given function.
+    pub fn build<P, F>(self, world: &mut World, position: F) -> Vec<Entity>
+    where
+        P: Position<N>,
+        F: Fn(Isometry3<N>) -> P,
+    {
+        // the bone colliders are only members of the self-collision group and
+        // whitelist all other groups; blacklisting the group instead would also
+        // exclude all colliders with the default CollisionGroups, which are
+        // members of every group
+        let whitelist = (0..=CollisionGroups::max_group_id())
+            .filter(|group_id| *group_id != self.self_collision_group)
+            .collect::<Vec<_>>();
+        let collision_groups = CollisionGroups::new()
+            .with_membership(&[self.self_collision_group])
+            .with_whitelist(&whitelist);
+
+        // bones are positioned relative to the ragdoll, so all of them share its
+        // rotation initially
+        let mut local_positions: Vec<Vector3<N>> = Vec::with_capacity(self.bones.len());
+        let mut entities: Vec<Entity> = Vec::with_capacity(self.bones.len());
+        for bone in &self.bones {
+            let local_position = match bone.parent {
+                Some(parent) => local_positions[parent] + bone.anchor.coords - bone.top(),
+                None => Vector3::zeros(),
+            };
+            local_positions.push(local_position);
+
+            let isometry = self.position
+                * Isometry3::translation(local_position.x, local_position.y, local_position.z);
+            let builder = world
+                .create_entity()
+                .with(position(isometry))
+                .with(PhysicsBodyBuilder::<N>::from(BodyStatus::Dynamic).build())
+                .with(
+                    PhysicsColliderBuilder::<N>::from(Shape::Capsule {
+                        half_height: bone.length / na::convert(2.0),
+                        radius: bone.radius,
+                    })
+                    .density(self.density)
+                    .collision_groups(collision_groups)
+                    .build(),
+                );
+
+            let entity = match bone.parent {
+                Some(parent) => {
+                    let local_anchor_1 = Point3::from(bone.top());
+                    let joint_type = match bone.joint {
+                        BoneJoint::Ball => JointType::Ball(BallJoint {
+                            local_anchor_1,
+                            local_anchor_2: bone.anchor,
+                        }),
+                        BoneJoint::Revolute { axis } => JointType::Revolute(RevoluteJoint {
+                            local_anchor_1,
+                            local_axis_1: axis,
+                            local_anchor_2: bone.anchor,
+                            local_axis_2: axis,
+                        }),
+                    };
+                    let mut joint_builder =
+                        PhysicsJointBuilder::from(joint_type).connected_entity(entities[parent]);
+                    if let Some(limits) = bone.limits {
+                        joint_builder = joint_builder.limits(limits);
+                    }
+
+                    builder.with_joint(joint_builder.build()).build()
+                }
+                None => builder.build(),
+            };
+            entities.push(entity);
+        }
+
+        entities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use super::{Bone, BoneJoint, RagdollBuilder};
+    use crate::{
+        bodies::Position,
+        colliders::Shape,
+        nalgebra::{Isometry3, Point3, Vector3},
+        parameters::Gravity,
+        physics_dispatcher,
+        PhysicsBody,
+        PhysicsCollider,
+        PhysicsColliderBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn settle_chain() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+
+        // create a static ground collider and drop a tilted 3-segment chain onto
+        // it, so it topples over
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 1.0, 10.0),
+                })
+                .build(),
+            )
+            .build();
+        let anchor = Point3::new(0.0, -0.6, 0.0);
+        let bones = RagdollBuilder::<f32>::new(
+            Isometry3::new(Vector3::new(0.0, 5.0, 0.0), Vector3::new(0.0, 0.0, 0.5)),
+            0,
+        )
+        .bone(Bone::root(1.0, 0.1))
+        .bone(Bone::child(0, anchor, 1.0, 0.1, BoneJoint::Ball))
+        .bone(Bone::child(1, anchor, 1.0, 0.1, BoneJoint::Ball))
+        .build(&mut world, SimplePosition::from_isometry);
+        assert_eq!(bones.len(), 3);
+
+        for _ in 0..600 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the chain came to rest on top of the ground without exploding
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        for bone in bones {
+            let translation = positions.get(bone).unwrap().isometry().translation.vector;
+            assert!(translation.y > 0.9 && translation.y < 2.0);
+            assert!(translation.x.abs() < 5.0 && translation.z.abs() < 5.0);
+
+            let velocity = physics_bodies.get(bone).unwrap().velocity;
+            assert!(velocity.linear.norm() < 0.1);
+        }
+    }
+
+    #[test]
+    fn stack_ragdolls() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+
+        // create a static ground collider with a lying single-bone ragdoll on it
+        // and drop a second ragdoll right on top of the first one
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 1.0, 10.0),
+                })
+                .build(),
+            )
+            .build();
+        let mut create_ragdoll = |height: f32, self_collision_group: usize| {
+            RagdollBuilder::<f32>::new(
+                Isometry3::new(
+                    Vector3::new(0.0, height, 0.0),
+                    Vector3::new(0.0, 0.0, std::f32::consts::FRAC_PI_2),
+                ),
+                self_collision_group,
+            )
+            .bone(Bone::root(1.0, 0.1))
+            .bone(Bone::child(
+                0,
+                Point3::new(0.0, -0.6, 0.0),
+                1.0,
+                0.1,
+                BoneJoint::Ball,
+            ))
+            .build(&mut world, SimplePosition::from_isometry)
+        };
+        let lower = create_ragdoll(1.1, 0);
+        let upper = create_ragdoll(1.5, 1);
+
+        // the bones of different ragdolls collide, those of the same one don't
+        {
+            let colliders = world.read_storage::<PhysicsCollider<f32>>();
+            let groups = |bone| colliders.get(bone).unwrap().collision_groups;
+            assert!(!groups(lower[0]).can_interact_with_groups(&groups(lower[1])));
+            assert!(groups(lower[0]).can_interact_with_groups(&groups(upper[0])));
+        }
+
+        for _ in 0..45 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the upper ragdoll landed on the lower one instead of falling through it
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        let height = |bone| positions.get(bone).unwrap().isometry().translation.vector.y;
+        for (lower, upper) in lower.into_iter().zip(upper) {
+            assert!(height(upper) > height(lower) + 0.15);
+        }
+    }
+}
@@ -0,0 +1,383 @@
+//! # Scene module
+//! Describes the physics `Component`s of a set of `Entity`s as a
+//! `PhysicsScene` and inserts them into freshly created `Entity`s again, e.g.
+//! to reload a level or to spawn a prefab several times. Enable the
+//! `serialize` feature to save and load scenes with `serde`.
+//!
+//! Scenes consist of the plain data descriptions of the `Component`s, i.e.
+//! `PhysicsBodyData`, `PhysicsColliderData` and `PhysicsJointData`.
+//! Colliders without a `BasicMaterial` are skipped on capture and
+//! `Shape::TriMesh`es fail to serialize.
+//!
+//! `PhysicsCollider`s and `PhysicsJoint`s refer to other `Entity`s through
+//! their `PhysicsParent` and `connected_entity`. These references are stored
+//! as the `Index` of the captured `Entity`s and rewritten by the
+//! `SceneLoader` through a map from the captured `Index`es to the newly
+//! allocated `Entity`s.
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use specs::{world::Index, Entities, Entity, Join, ReadStorage, World, WorldExt, WriteStorage};
+
+use crate::{
+    bodies::{PhysicsBodyData, Position},
+    colliders::PhysicsColliderData,
+    joints::PhysicsJointData,
+    nalgebra::{Isometry3, RealField},
+    PhysicsBody,
+    PhysicsCollider,
+    PhysicsJoint,
+    PhysicsParent,
+};
+
+/// The captured physics `Component`s of a single `Entity`.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntityScene<N: RealField> {
+    /// The `Index` of the `Entity` the `Component`s were captured from.
+    pub entity: Index,
+    /// The isometry of the `Position` of the `Entity`.
+    pub position: Isometry3<N>,
+    pub body: Option<PhysicsBodyData<N>>,
+    pub collider: Option<PhysicsColliderData<N>>,
+    /// The `Index` of the `Entity` of the `PhysicsParent`.
+    pub parent: Option<Index>,
+    pub joint: Option<PhysicsJointData<N>>,
+}
+
+/// A `PhysicsScene` contains the physics `Component`s of all `Entity`s with
+/// a `Position` and a `PhysicsBody`, `PhysicsCollider` or `PhysicsJoint`.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicsScene<N: RealField> {
+    pub entities: Vec<EntityScene<N>>,
+}
+
+impl<N: RealField> PhysicsScene<N> {
+    /// Captures the physics `Component`s of all `Entity`s of the given
+    /// `World`.
+    pub fn capture<P: Position<N>>(world: &World) -> Self {
+        let (
+            entities,
+            positions,
+            physics_bodies,
+            physics_colliders,
+            physics_parents,
+            physics_joints,
+        ) = world.system_data::<(
+            Entities,
+            ReadStorage<P>,
+            ReadStorage<PhysicsBody<N>>,
+            ReadStorage<PhysicsCollider<N>>,
+            ReadStorage<PhysicsParent>,
+            ReadStorage<PhysicsJoint<N>>,
+        )>();
+
+        let entities = (
+            &entities,
+            &positions,
+            physics_bodies.maybe(),
+            physics_colliders.maybe(),
+            physics_parents.maybe(),
+            physics_joints.maybe(),
+        )
+            .join()
+            .filter(|(_, _, body, collider, _, joint)| {
+                body.is_some() || collider.is_some() || joint.is_some()
+            })
+            .map(
+                |(entity, position, body, collider, parent, joint)| EntityScene {
+                    entity: entity.id(),
+                    position: *position.isometry(),
+                    body: body.map(PhysicsBody::data),
+                    collider: collider.and_then(|collider| {
+                        let data = collider.data();
+                        if data.is_none() {
+                            warn!(
+                                "Unable to capture PhysicsCollider of {:?} without a BasicMaterial",
+                                entity
+                            );
+                        }
+                        data
+                    }),
+                    parent: parent.map(|parent| parent.entity.id()),
+                    joint: joint.map(PhysicsJoint::data),
+                },
+            )
+            .collect();
+
+        Self { entities }
+    }
+}
+
+/// The `SceneLoader` inserts the `Component`s of a `PhysicsScene` into the
+/// `Entity`s their captured `Index`es are mapped to, rewriting all references
+/// to other captured `Entity`s on the way.
+///
+/// Captured `Entity`s missing from the map are skipped, as are colliders
+/// whose `PhysicsParent` and joints whose `connected_entity` is missing from
+/// it; they would otherwise silently be attached to the ground.
+pub struct SceneLoader {
+    mapping: HashMap<Index, Entity>,
+}
+
+impl SceneLoader {
+    /// Creates a new `SceneLoader` from a map of the captured `Index`es to
+    /// the newly allocated `Entity`s.
+    pub fn new(mapping: HashMap<Index, Entity>) -> Self {
+        Self { mapping }
+    }
+
+    /// Inserts the `Component`s of the given `PhysicsScene` into the mapped
+    /// `Entity`s of the `World`. The `Position` of each `Entity` is created
+    /// from its captured `Isometry3` by the given function.
+    pub fn load<N, P, F>(&self, world: &World, scene: &PhysicsScene<N>, position: F)
+    where
+        N: RealField,
+        P: Position<N>,
+        F: Fn(Isometry3<N>) -> P,
+    {
+        let (
+            mut positions,
+            mut physics_bodies,
+            mut physics_colliders,
+            mut physics_parents,
+            mut physics_joints,
+        ) = world.system_data::<(
+            WriteStorage<P>,
+            WriteStorage<PhysicsBody<N>>,
+            WriteStorage<PhysicsCollider<N>>,
+            WriteStorage<PhysicsParent>,
+            WriteStorage<PhysicsJoint<N>>,
+        )>();
+
+        for entity_scene in &scene.entities {
+            let entity = match self.mapping.get(&entity_scene.entity) {
+                Some(entity) => *entity,
+                None => {
+                    warn!(
+                        "Unable to load unmapped Entity {} of PhysicsScene",
+                        entity_scene.entity
+                    );
+                    continue;
+                }
+            };
+
+            // inserting into dead Entities fails, which leaves nothing to rebind
+            if positions
+                .insert(entity, position(entity_scene.position))
+                .is_err()
+            {
+                warn!("Unable to load PhysicsScene into dead Entity {:?}", entity);
+                continue;
+            }
+
+            if let Some(body) = &entity_scene.body {
+                physics_bodies.insert(entity, body.build()).unwrap();
+            }
+
+            if let Some(collider) = &entity_scene.collider {
+                match self.rebind_parent(entity_scene.parent) {
+                    Ok(parent) => {
+                        if let Some(parent) = parent {
+                            physics_parents.insert(entity, parent).unwrap();
+                        }
+                        physics_colliders.insert(entity, collider.build()).unwrap();
+                    }
+                    Err(parent) => warn!(
+                        "Unable to load PhysicsCollider of {:?}, its parent {} is unmapped",
+                        entity, parent
+                    ),
+                }
+            }
+
+            if let Some(joint) = &entity_scene.joint {
+                let connected_entity = match joint.connected_entity {
+                    Some(connected_entity) => match self.mapping.get(&connected_entity) {
+                        Some(mapped_entity) => Some(*mapped_entity),
+                        None => {
+                            warn!(
+                                "Unable to load PhysicsJoint of {:?}, its connected Entity {} is unmapped",
+                                entity, connected_entity
+                            );
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                physics_joints
+                    .insert(entity, joint.build(connected_entity))
+                    .unwrap();
+            }
+        }
+    }
+
+    // maps the captured PhysicsParent to the newly allocated Entity; returns the
+    // captured parent Index if it is unmapped
+    fn rebind_parent(&self, parent: Option<Index>) -> Result<Option<PhysicsParent>, Index> {
+        match parent {
+            Some(parent) => match self.mapping.get(&parent) {
+                Some(entity) => Ok(Some(PhysicsParent { entity: *entity })),
+                None => Err(parent),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use specs::prelude::*;
+
+    use super::{PhysicsScene, SceneLoader};
+    use crate::{
+        colliders::Shape,
+        joints::{BallJoint, JointType},
+        nalgebra::{Isometry3, Point3},
+        nphysics::object::BodyStatus,
+        physics_dispatcher,
+        EntityBuilderExt,
+        Physics,
+        PhysicsBodyBuilder,
+        PhysicsColliderBuilder,
+        PhysicsJoint,
+        PhysicsJointBuilder,
+        SimplePosition,
+    };
+
+    // captures a pair of jointed bodies, the second one carrying a separate
+    // collider Entity; returns the scene along with the captured Entities
+    fn capture_jointed_pair() -> (PhysicsScene<f32>, [Entity; 3]) {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        let first = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 2.0, 0.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .build();
+        let second = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 1.0, 0.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with_joint(
+                PhysicsJointBuilder::<f32>::from(JointType::Ball(BallJoint {
+                    local_anchor_1: Point3::new(0.0, 0.5, 0.0),
+                    local_anchor_2: Point3::new(0.0, -0.5, 0.0),
+                }))
+                .connected_entity(first)
+                .build(),
+            )
+            .build();
+        let collider = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 1.0, 0.0)))
+            .attach_collider(
+                second,
+                PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+
+        let scene = PhysicsScene::<f32>::capture::<SimplePosition<f32>>(&world);
+        assert_eq!(scene.entities.len(), 3);
+        (scene, [first, second, collider])
+    }
+
+    // loads the scene of capture_jointed_pair into a fresh World, whose Entities
+    // are allocated differently, and asserts that the joint reconnects the new
+    // bodies and that the collider is attached to the new second body
+    fn load_jointed_pair(scene: &PhysicsScene<f32>, [first, second, collider]: [Entity; 3]) {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        world.create_entity().build();
+        let mut mapping = HashMap::new();
+        for captured in &[collider, second, first] {
+            mapping.insert(captured.id(), world.create_entity().build());
+        }
+        SceneLoader::new(mapping.clone()).load(&world, scene, SimplePosition::from_isometry);
+        dispatcher.dispatch(&world);
+
+        let (first, second, collider) = (
+            mapping[&first.id()],
+            mapping[&second.id()],
+            mapping[&collider.id()],
+        );
+        let physics_joints = world.read_storage::<PhysicsJoint<f32>>();
+        assert_eq!(
+            physics_joints.get(second).unwrap().connected_entity,
+            Some(first)
+        );
+
+        let physics = world.read_resource::<Physics<f32>>();
+        let entities = world.entities();
+        let mut connected_bodies = physics
+            .connected_bodies(&entities, second)
+            .into_iter()
+            .collect::<Vec<_>>();
+        connected_bodies.sort();
+        let mut expected = vec![first, second];
+        expected.sort();
+        assert_eq!(connected_bodies, expected);
+        assert_eq!(physics.collider_parent(&entities, collider), Some(second));
+    }
+
+    #[test]
+    fn reload_jointed_pair() {
+        let (scene, captured) = capture_jointed_pair();
+        load_jointed_pair(&scene, captured);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn save_and_load_jointed_pair() {
+        let (scene, captured) = capture_jointed_pair();
+
+        // save the scene and load it again before reloading it
+        let saved = serde_json::to_string(&scene).unwrap();
+        let loaded: PhysicsScene<f32> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded, scene);
+        load_jointed_pair(&loaded, captured);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn reject_tri_mesh() {
+        use crate::colliders::{IntoMesh, MeshData, MeshHandle};
+
+        #[derive(Clone)]
+        struct Empty;
+
+        impl IntoMesh for Empty {
+            type N = f32;
+
+            fn points(&self) -> MeshData<f32> {
+                (Vec::new(), Vec::new(), None)
+            }
+        }
+
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::TriMesh {
+                    handle: MeshHandle::new(Empty),
+                })
+                .build(),
+            )
+            .build();
+
+        // the mesh of a TriMesh is a trait object and can't be saved
+        let scene = PhysicsScene::<f32>::capture::<SimplePosition<f32>>(&world);
+        assert!(serde_json::to_string(&scene).is_err());
+    }
+}
@@ -0,0 +1,529 @@
+//! # Snapshot module
+//! Captures and restores the dynamic state of all `PhysicsBody`s along with
+//! all `PhysicsCollider`s, e.g. for save games. Enable the `serialize`
+//! feature to (de)serialize snapshots with `serde`.
+//!
+//! Snapshots contain the pose, velocity and sleep state of [RigidBody][]s and
+//! the `PhysicsColliderData` of colliders with the built-in shapes and a
+//! `BasicMaterial`; other colliders are skipped on capture. `PhysicsBody` and
+//! `PhysicsJoint` `Component`s are not part of a snapshot, so they have to
+//! exist before it is restored, e.g. by loading a `PhysicsScene` first.
+//!
+//! Bodies and colliders are captured with the `Index` of their `Entity`,
+//! which is meaningless in another `World`. They are restored into the
+//! `Entity`s given by an explicit map from the captured `Index`es instead,
+//! such as the one used to load the `PhysicsScene`.
+//!
+//! For networked physics, snapshots can be delta-encoded via
+//! `PhysicsSnapshot::diff` and `PhysicsSnapshot::apply_delta`. As `Entity`
+//! indices differ between machines, bodies are identified by their `NetworkId`
+//! `Component` instead.
+//!
+//! [RigidBody]: https://www.nphysics.org/rigid_body_simulations_with_contacts/#rigid-bodies
+
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use specs::{
+    world::Index,
+    Component,
+    DenseVecStorage,
+    Entities,
+    Entity,
+    Join,
+    ReadExpect,
+    ReadStorage,
+    World,
+    WorldExt,
+    WriteExpect,
+    WriteStorage,
+};
+
+use crate::{
+    bodies::{PhysicsBody, Position},
+    colliders::{PhysicsCollider, PhysicsColliderData},
+    nalgebra::{Isometry3, RealField, Vector3},
+    nphysics::{algebra::Velocity3, object::Body},
+    Physics,
+};
+
+/// The `NetworkId` `Component` identifies an `Entity` across machines. It
+/// has to be assigned by the user, e.g. by the server, and is used to match
+/// the bodies of snapshots and deltas instead of the `Entity` index.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NetworkId(pub u64);
+
+impl Component for NetworkId {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The captured state of a single `RigidBody`, keyed by the `Index` of its
+/// `Entity` and its `NetworkId`, if any.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BodySnapshot<N: RealField> {
+    pub entity: Index,
+    pub network_id: Option<NetworkId>,
+    pub position: Isometry3<N>,
+    pub linear_velocity: Vector3<N>,
+    pub angular_velocity: Vector3<N>,
+    pub sleeping: bool,
+}
+
+/// The captured `PhysicsColliderData` of a single `PhysicsCollider`, keyed
+/// by the `Index` of its `Entity` and its `NetworkId`, if any.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColliderSnapshot<N: RealField> {
+    pub entity: Index,
+    pub network_id: Option<NetworkId>,
+    pub collider: PhysicsColliderData<N>,
+}
+
+/// A `PhysicsSnapshot` contains the position, velocity and sleep state of all
+/// `RigidBody`s of the physics world as well as the description of all
+/// `PhysicsCollider`s at the time of its capture.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicsSnapshot<N: RealField> {
+    pub bodies: Vec<BodySnapshot<N>>,
+    pub colliders: Vec<ColliderSnapshot<N>>,
+}
+
+/// A `PhysicsDelta` contains the `BodySnapshot`s of all bodies with a
+/// `NetworkId` that changed between two `PhysicsSnapshot`s, see
+/// `PhysicsSnapshot::diff`.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicsDelta<N: RealField> {
+    pub bodies: Vec<BodySnapshot<N>>,
+}
+
+impl<N: RealField> BodySnapshot<N> {
+    // whether the pose, velocity or sleep state differ by more than threshold
+    fn differs(&self, other: &Self, threshold: N) -> bool {
+        self.sleeping != other.sleeping
+            || (self.position.translation.vector - other.position.translation.vector).norm()
+                > threshold
+            || self.position.rotation.angle_to(&other.position.rotation) > threshold
+            || (self.linear_velocity - other.linear_velocity).norm() > threshold
+            || (self.angular_velocity - other.angular_velocity).norm() > threshold
+    }
+}
+
+impl<N: RealField> PhysicsSnapshot<N> {
+    /// Captures the current state of all `RigidBody`s and `PhysicsCollider`s
+    /// of the given `World`.
+    pub fn capture(world: &World) -> Self {
+        let (entities, physics_bodies, physics_colliders, network_ids, physics) = world
+            .system_data::<(
+                Entities,
+                ReadStorage<PhysicsBody<N>>,
+                ReadStorage<PhysicsCollider<N>>,
+                ReadStorage<NetworkId>,
+                ReadExpect<Physics<N>>,
+            )>();
+
+        let bodies = (&entities, &physics_bodies, network_ids.maybe())
+            .join()
+            .filter_map(|(entity, physics_body, network_id)| {
+                let rigid_body = physics.bodies.rigid_body(physics_body.handle?)?;
+                Some(BodySnapshot {
+                    entity: entity.id(),
+                    network_id: network_id.cloned(),
+                    position: *rigid_body.position(),
+                    linear_velocity: rigid_body.velocity().linear,
+                    angular_velocity: rigid_body.velocity().angular,
+                    sleeping: !rigid_body.is_active(),
+                })
+            })
+            .collect();
+
+        let colliders = (&entities, &physics_colliders, network_ids.maybe())
+            .join()
+            .filter_map(|(entity, physics_collider, network_id)| {
+                let collider = physics_collider.data();
+                if collider.is_none() {
+                    warn!(
+                        "Unable to capture PhysicsCollider of {:?} without a BasicMaterial",
+                        entity
+                    );
+                }
+
+                Some(ColliderSnapshot {
+                    entity: entity.id(),
+                    network_id: network_id.cloned(),
+                    collider: collider?,
+                })
+            })
+            .collect();
+
+        Self { bodies, colliders }
+    }
+
+    /// Returns a `PhysicsDelta` containing the bodies with a `NetworkId` of
+    /// the `other` `PhysicsSnapshot` whose pose, velocity or sleep state
+    /// changed by more than `threshold` compared to this snapshot, or which
+    /// are missing from this snapshot. Bodies without a `NetworkId` are
+    /// ignored.
+    pub fn diff(&self, other: &Self, threshold: N) -> PhysicsDelta<N> {
+        let previous_bodies = self.bodies_by_network_id();
+        let bodies = other
+            .bodies
+            .iter()
+            .filter(|body| match body.network_id {
+                Some(network_id) => previous_bodies
+                    .get(&network_id)
+                    .map_or(true, |previous_body| previous_body.differs(body, threshold)),
+                None => false,
+            })
+            .cloned()
+            .collect();
+
+        PhysicsDelta { bodies }
+    }
+
+    /// Applies the given `PhysicsDelta` to this snapshot, replacing the
+    /// bodies with matching `NetworkId`s and adding new ones.
+    pub fn apply_delta(&mut self, delta: &PhysicsDelta<N>) {
+        for body in &delta.bodies {
+            match self
+                .bodies
+                .iter_mut()
+                .find(|previous_body| previous_body.network_id == body.network_id)
+            {
+                Some(previous_body) => {
+                    // keep the local Entity index, it differs between machines
+                    *previous_body = BodySnapshot {
+                        entity: previous_body.entity,
+                        ..body.clone()
+                    };
+                }
+                None => self.bodies.push(body.clone()),
+            }
+        }
+    }
+
+    fn bodies_by_network_id(&self) -> HashMap<NetworkId, &BodySnapshot<N>> {
+        self.bodies
+            .iter()
+            .filter_map(|body| body.network_id.map(|network_id| (network_id, body)))
+            .collect()
+    }
+
+    /// Restores the captured state into the `RigidBody`s of the given `World`
+    /// as well as their `PhysicsBody` and `Position` `Component`s, and
+    /// replaces the `PhysicsCollider`s that differ from the captured ones.
+    ///
+    /// Bodies and colliders with a `NetworkId` are restored into the `Entity`
+    /// with the same `NetworkId`, all others into the `Entity` their captured
+    /// `Index` is mapped to by `entity_map`. Bodies without a `PhysicsBody` to
+    /// restore into are skipped, as are unmapped and deleted `Entity`s.
+    pub fn restore<P: Position<N>>(&self, world: &World, entity_map: &HashMap<Index, Entity>) {
+        let (
+            entities,
+            network_ids,
+            mut physics_bodies,
+            mut physics_colliders,
+            mut positions,
+            mut physics,
+        ) = world.system_data::<(
+            Entities,
+            ReadStorage<NetworkId>,
+            WriteStorage<PhysicsBody<N>>,
+            WriteStorage<PhysicsCollider<N>>,
+            WriteStorage<P>,
+            WriteExpect<Physics<N>>,
+        )>();
+        let network_entities = (&entities, &network_ids)
+            .join()
+            .map(|(entity, network_id)| (*network_id, entity))
+            .collect::<HashMap<NetworkId, Entity>>();
+        let resolve = |index: Index, network_id: Option<NetworkId>| {
+            let entity = match network_id {
+                Some(network_id) => network_entities.get(&network_id),
+                None => entity_map.get(&index),
+            };
+            match entity {
+                Some(entity) if entities.is_alive(*entity) => Some(*entity),
+                Some(entity) => {
+                    warn!("Unable to restore into deleted Entity {:?}", entity);
+                    None
+                }
+                None => {
+                    warn!(
+                        "Unable to restore unmapped Entity {} with {:?}",
+                        index, network_id
+                    );
+                    None
+                }
+            }
+        };
+
+        for body in &self.bodies {
+            let entity = match resolve(body.entity, body.network_id) {
+                Some(entity) => entity,
+                None => continue,
+            };
+
+            let physics_body = match physics_bodies.get_mut(entity) {
+                Some(physics_body) => physics_body,
+                None => continue,
+            };
+            let rigid_body = match physics_body
+                .handle
+                .and_then(|handle| physics.bodies.rigid_body_mut(handle))
+            {
+                Some(rigid_body) => rigid_body,
+                None => continue,
+            };
+
+            let velocity = Velocity3::new(body.linear_velocity, body.angular_velocity);
+            rigid_body.set_position(body.position);
+            rigid_body.set_velocity(velocity);
+            if body.sleeping {
+                rigid_body.deactivate();
+            } else {
+                rigid_body.activate();
+            }
+
+            physics_body.velocity = velocity;
+            if let Some(position) = positions.get_mut(entity) {
+                position.set_isometry(&body.position);
+            }
+        }
+
+        // unchanged colliders are kept, as replacing them drops their contacts
+        for collider in &self.colliders {
+            let entity = match resolve(collider.entity, collider.network_id) {
+                Some(entity) => entity,
+                None => continue,
+            };
+
+            let unchanged = physics_colliders
+                .get(entity)
+                .and_then(PhysicsCollider::data)
+                .map_or(false, |data| data == collider.collider);
+            if !unchanged {
+                physics_colliders
+                    .insert(entity, collider.collider.build())
+                    .unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use specs::prelude::*;
+
+    use super::{NetworkId, PhysicsSnapshot};
+    use crate::{
+        colliders::Shape,
+        nalgebra::Isometry3,
+        nphysics::{algebra::Velocity3, object::BodyStatus},
+        physics_dispatcher,
+        PhysicsBodyBuilder,
+        PhysicsCollider,
+        PhysicsColliderBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn capture_and_restore() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a moving Entity and capture its state
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(1.0, 0.0, 0.0))
+                    .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+        let snapshot = PhysicsSnapshot::<f32>::capture(&world);
+        let position = world
+            .read_storage::<SimplePosition<f32>>()
+            .get(entity)
+            .unwrap()
+            .0;
+
+        // perturb the simulation, then restore the snapshot
+        for _ in 0..10 {
+            dispatcher.dispatch(&world);
+        }
+        let entity_map = vec![(entity.id(), entity)].into_iter().collect();
+        snapshot.restore::<SimplePosition<f32>>(&world, &entity_map);
+        assert_eq!(
+            world
+                .read_storage::<SimplePosition<f32>>()
+                .get(entity)
+                .unwrap()
+                .0,
+            position
+        );
+        assert_eq!(PhysicsSnapshot::<f32>::capture(&world), snapshot);
+    }
+
+    #[test]
+    fn restore_colliders() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a static collider and capture it
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+        let snapshot = PhysicsSnapshot::<f32>::capture(&world);
+        assert_eq!(snapshot.colliders.len(), 1);
+
+        // delete the Entity and create a new one, which reuses its index
+        world.delete_entity(entity).unwrap();
+        world.maintain();
+        dispatcher.dispatch(&world);
+        let reused = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .build();
+        assert_eq!(reused.id(), entity.id());
+
+        // the unmapped collider isn't restored into the unrelated Entity
+        snapshot.restore::<SimplePosition<f32>>(&world, &HashMap::new());
+        assert!(world
+            .read_storage::<PhysicsCollider<f32>>()
+            .get(reused)
+            .is_none());
+
+        // but it is once the Entity is mapped explicitly
+        let entity_map = vec![(entity.id(), reused)].into_iter().collect();
+        snapshot.restore::<SimplePosition<f32>>(&world, &entity_map);
+        assert_eq!(
+            *world
+                .read_storage::<PhysicsCollider<f32>>()
+                .get(reused)
+                .unwrap()
+                .shape(),
+            Shape::Ball { radius: 1.0 }
+        );
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn serialize_and_restore() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a moving Entity with a collider and save a snapshot of it
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(false)
+                    .velocity(Velocity3::linear(1.0, 0.0, 0.0))
+                    .build(),
+            )
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+        for _ in 0..5 {
+            dispatcher.dispatch(&world);
+        }
+        let snapshot = PhysicsSnapshot::<f32>::capture(&world);
+        let saved = serde_json::to_string(&snapshot).unwrap();
+
+        // load it again and restore it into a fresh World, whose Entities are
+        // allocated differently
+        let loaded: PhysicsSnapshot<f32> = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded, snapshot);
+
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .build();
+        let restored = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(false)
+                    .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+        let entity_map = vec![(entity.id(), restored)].into_iter().collect();
+        loaded.restore::<SimplePosition<f32>>(&world, &entity_map);
+        dispatcher.dispatch(&world);
+
+        // the mapped Entity continues where the saved one was captured and has
+        // the saved collider attached
+        let snapshot = snapshot.bodies[0].clone();
+        let position = world
+            .read_storage::<SimplePosition<f32>>()
+            .get(restored)
+            .unwrap()
+            .0;
+        assert!(position.translation.vector.x > snapshot.position.translation.vector.x);
+        assert!(world
+            .read_storage::<PhysicsCollider<f32>>()
+            .get(restored)
+            .is_some());
+    }
+
+    #[test]
+    fn diff_and_apply_delta() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a moving and a resting Entity, both replicated via a NetworkId
+        let mut create_entity = |network_id, velocity| {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::identity()))
+                .with(
+                    PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                        .velocity(velocity)
+                        .build(),
+                )
+                .with(NetworkId(network_id))
+                .build()
+        };
+        create_entity(1, Velocity3::linear(1.0, 0.0, 0.0));
+        create_entity(2, Velocity3::zero());
+        dispatcher.dispatch(&world);
+        let first = PhysicsSnapshot::<f32>::capture(&world);
+
+        for _ in 0..5 {
+            dispatcher.dispatch(&world);
+        }
+        let second = PhysicsSnapshot::<f32>::capture(&world);
+
+        // only the moving body is encoded in the delta
+        let delta = first.diff(&second, 1.0e-4);
+        assert_eq!(delta.bodies.len(), 1);
+        assert_eq!(delta.bodies[0].network_id, Some(NetworkId(1)));
+
+        // applying the delta to the first snapshot reconstructs the second one
+        let mut reconstructed = first.clone();
+        reconstructed.apply_delta(&delta);
+        assert_eq!(reconstructed, second);
+    }
+}
@@ -0,0 +1,593 @@
+use std::marker::PhantomData;
+
+use specs::{
+    storage::ComponentEvent,
+    world::Index,
+    Entities,
+    Join,
+    Read,
+    ReadStorage,
+    ReaderId,
+    System,
+    SystemData,
+    World,
+    WriteExpect,
+    WriteStorage,
+};
+
+use crate::{
+    bodies::Position,
+    forces::{Attractor, BodyGravity, DragForce, Falloff, SpringForce, WindForce},
+    nalgebra::{Point3, RealField, Unit, Vector3},
+    nphysics::{
+        algebra::{Force3, ForceType},
+        object::{Body, BodyStatus, DefaultBodyHandle},
+    },
+    parameters::{is_stepping, PhysicsPaused, StepOnce},
+    Physics,
+};
+
+use super::iterate_component_events;
+
+/// The `ApplyForcesSystem` applies the continuous forces described by the
+/// `Component`s of the `forces` module to their `RigidBody`s. It has to run
+/// before the `PhysicsStepperSystem`, as the applied forces are cleared after
+/// every simulation step. No forces are applied while the simulation is
+/// paused, so they don't accumulate until it resumes.
+pub struct ApplyForcesSystem<N, P> {
+    body_gravities_reader_id: Option<ReaderId<ComponentEvent>>,
+
+    n_marker: PhantomData<N>,
+    p_marker: PhantomData<P>,
+}
+
+impl<'s, N, P> System<'s> for ApplyForcesSystem<N, P>
+where
+    N: RealField,
+    P: Position<N>,
+{
+    type SystemData = (
+        Entities<'s>,
+        Option<Read<'s, PhysicsPaused>>,
+        Option<Read<'s, StepOnce>>,
+        ReadStorage<'s, P>,
+        ReadStorage<'s, DragForce<N>>,
+        ReadStorage<'s, WindForce<N>>,
+        ReadStorage<'s, SpringForce<N>>,
+        ReadStorage<'s, Attractor<N>>,
+        ReadStorage<'s, BodyGravity<N>>,
+        WriteExpect<'s, Physics<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            paused,
+            step_once,
+            positions,
+            drag_forces,
+            wind_forces,
+            spring_forces,
+            attractors,
+            body_gravities,
+            mut physics,
+        ) = data;
+
+        // enable the world gravity of bodies whose BodyGravity was removed again;
+        // this is done even while paused, as it doesn't apply any force
+        let (.., removed_body_gravities) = iterate_component_events(
+            &body_gravities,
+            self.body_gravities_reader_id.as_mut().unwrap(),
+        );
+        for id in &removed_body_gravities {
+            if !body_gravities.mask().contains(id) {
+                restore_gravity(id, &mut physics);
+            }
+        }
+
+        // nphysics only clears applied forces when stepping, so forces applied
+        // while paused would be released all at once on resume
+        if !is_stepping(paused.as_deref(), step_once.as_deref()) {
+            return;
+        }
+
+        // apply air drag relative to the wind velocity, if any
+        for (entity, drag_force, wind_force) in
+            (&entities, &drag_forces, wind_forces.maybe()).join()
+        {
+            let wind_velocity =
+                wind_force.map_or(Vector3::zeros(), |wind_force| wind_force.velocity);
+            apply_drag_force(entity.id(), &mut physics, drag_force, &wind_velocity);
+        }
+
+        // apply springs between both of their endpoints
+        for (entity, spring_force) in (&entities, &spring_forces).join() {
+            if !entities.is_alive(spring_force.connected_entity) {
+                debug!(
+                    "Skipping SpringForce of {:?}, connected Entity {:?} no longer exists",
+                    entity, spring_force.connected_entity
+                );
+                continue;
+            }
+            apply_spring_force(entity.id(), &mut physics, spring_force);
+        }
+
+        // pull all other bodies towards the attractors
+        for (entity, position, attractor) in (&entities, &positions, &attractors).join() {
+            apply_attractor(entity.id(), &mut physics, position, attractor);
+        }
+
+        // replace the world gravity with the gravity of the body
+        for (entity, body_gravity) in (&entities, &body_gravities).join() {
+            apply_body_gravity(entity.id(), &mut physics, body_gravity);
+        }
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("ApplyForcesSystem.setup");
+        Self::SystemData::setup(res);
+
+        // initialise required resources
+        res.entry::<Physics<N>>().or_insert_with(Physics::default);
+
+        // register reader id for the BodyGravity storage
+        let mut body_gravity_storage: WriteStorage<BodyGravity<N>> = SystemData::fetch(&res);
+        self.body_gravities_reader_id = Some(body_gravity_storage.register_reader());
+    }
+}
+
+impl<N, P> Default for ApplyForcesSystem<N, P>
+where
+    N: RealField,
+    P: Position<N>,
+{
+    fn default() -> Self {
+        Self {
+            body_gravities_reader_id: None,
+            n_marker: PhantomData,
+            p_marker: PhantomData,
+        }
+    }
+}
+
+fn apply_drag_force<N>(
+    id: Index,
+    physics: &mut Physics<N>,
+    drag_force: &DragForce<N>,
+    wind_velocity: &Vector3<N>,
+) where
+    N: RealField,
+{
+    let rigid_body = match physics
+        .body_handles
+        .get(&id)
+        .cloned()
+        .and_then(|handle| physics.bodies.rigid_body_mut(handle))
+    {
+        Some(rigid_body) if rigid_body.status() == BodyStatus::Dynamic => rigid_body,
+        _ => return,
+    };
+
+    let relative_velocity = rigid_body.velocity().linear - wind_velocity;
+    rigid_body.apply_force(
+        0,
+        &Force3::linear(relative_velocity * -drag_force.coefficient),
+        ForceType::Force,
+        true,
+    );
+}
+
+fn apply_spring_force<N>(id: Index, physics: &mut Physics<N>, spring_force: &SpringForce<N>)
+where
+    N: RealField,
+{
+    let handle_1 = physics.body_handles.get(&id).cloned();
+    let handle_2 = physics
+        .body_handles
+        .get(&spring_force.connected_entity.id())
+        .cloned();
+    let (handle_1, handle_2) = match (handle_1, handle_2) {
+        (Some(handle_1), Some(handle_2)) => (handle_1, handle_2),
+        _ => return,
+    };
+
+    // fetch the world anchors and their velocities of both endpoints
+    let anchor = |handle, local_anchor: &Point3<N>| {
+        physics.bodies.rigid_body(handle).map(|rigid_body| {
+            let point = rigid_body.position() * local_anchor;
+            let velocity = rigid_body.velocity().linear
+                + rigid_body
+                    .velocity()
+                    .angular
+                    .cross(&(point - rigid_body.center_of_mass()));
+            (point, velocity)
+        })
+    };
+    let (point_1, velocity_1) = match anchor(handle_1, &spring_force.local_anchor_1) {
+        Some(anchor) => anchor,
+        None => return,
+    };
+    let (point_2, velocity_2) = match anchor(handle_2, &spring_force.local_anchor_2) {
+        Some(anchor) => anchor,
+        None => return,
+    };
+
+    // the spring acts along the line between both anchors and is undefined if they
+    // coincide
+    let (direction, length) = match Unit::try_new_and_get(point_2 - point_1, N::default_epsilon()) {
+        Some(direction) => direction,
+        None => return,
+    };
+    let magnitude = spring_force.stiffness * (length - spring_force.rest_length)
+        + spring_force.damping * (velocity_2 - velocity_1).dot(&direction);
+    let force = direction.into_inner() * magnitude;
+
+    apply_force_at_point(physics, handle_1, &force, &point_1);
+    apply_force_at_point(physics, handle_2, &-force, &point_2);
+}
+
+fn apply_attractor<N, P>(
+    id: Index,
+    physics: &mut Physics<N>,
+    position: &P,
+    attractor: &Attractor<N>,
+) where
+    N: RealField,
+    P: Position<N>,
+{
+    let center = Point3::from(position.isometry().translation.vector);
+    let own_handle = physics.body_handles.get(&id).cloned();
+    let handles = physics
+        .body_handles
+        .values()
+        .cloned()
+        .filter(|handle| Some(*handle) != own_handle)
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        let rigid_body = match physics.bodies.rigid_body_mut(handle) {
+            Some(rigid_body) if rigid_body.status() == BodyStatus::Dynamic => rigid_body,
+            _ => continue,
+        };
+        let (direction, distance) =
+            match Unit::try_new_and_get(center - rigid_body.center_of_mass(), N::default_epsilon())
+            {
+                Some(direction) => direction,
+                None => continue,
+            };
+
+        let clamped_distance = distance.max(attractor.min_distance);
+        let magnitude = match attractor.falloff {
+            Falloff::InverseSquare => attractor.strength / (clamped_distance * clamped_distance),
+            Falloff::Linear { radius } if distance < radius => {
+                attractor.strength * (N::one() - clamped_distance / radius)
+            }
+            Falloff::Linear { .. } => continue,
+        };
+        rigid_body.apply_force(
+            0,
+            &Force3::linear(direction.into_inner() * magnitude),
+            ForceType::Force,
+            true,
+        );
+    }
+}
+
+fn apply_body_gravity<N>(id: Index, physics: &mut Physics<N>, body_gravity: &BodyGravity<N>)
+where
+    N: RealField,
+{
+    let rigid_body = match physics
+        .body_handles
+        .get(&id)
+        .cloned()
+        .and_then(|handle| physics.bodies.rigid_body_mut(handle))
+    {
+        Some(rigid_body) if rigid_body.status() == BodyStatus::Dynamic => rigid_body,
+        _ => return,
+    };
+
+    if rigid_body.gravity_enabled() {
+        rigid_body.enable_gravity(false);
+    }
+
+    // only a non-zero field keeps waking the body; a zero field lets it sleep
+    let auto_wake_up = body_gravity.0 != Vector3::zeros();
+    rigid_body.apply_force(
+        0,
+        &Force3::linear(body_gravity.0),
+        ForceType::AccelerationChange,
+        auto_wake_up,
+    );
+}
+
+fn restore_gravity<N>(id: Index, physics: &mut Physics<N>)
+where
+    N: RealField,
+{
+    if let Some(rigid_body) = physics
+        .body_handles
+        .get(&id)
+        .cloned()
+        .and_then(|handle| physics.bodies.rigid_body_mut(handle))
+    {
+        rigid_body.enable_gravity(true);
+        rigid_body.activate();
+    }
+}
+
+fn apply_force_at_point<N>(
+    physics: &mut Physics<N>,
+    handle: DefaultBodyHandle,
+    force: &Vector3<N>,
+    point: &Point3<N>,
+) where
+    N: RealField,
+{
+    if let Some(rigid_body) = physics.bodies.rigid_body_mut(handle) {
+        if rigid_body.status() == BodyStatus::Dynamic {
+            rigid_body.apply_force_at_point(0, force, point, ForceType::Force, true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        all_physics_dispatcher,
+        forces::{Attractor, BodyGravity, DragForce, Falloff, SpringForce, WindForce},
+        nalgebra::{Isometry3, Vector3},
+        nphysics::{algebra::Velocity3, object::BodyStatus},
+        parameters::{Gravity, PhysicsPaused},
+        PhysicsBody,
+        PhysicsBodyBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn terminal_velocity() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a falling Entity slowed down by air drag
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .mass(1.0)
+                    .build(),
+            )
+            .with(DragForce::<f32> { coefficient: 2.0 })
+            .build();
+        for _ in 0..300 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the velocity stabilises where drag and gravity cancel each other out
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let velocity = physics_bodies.get(entity).unwrap().velocity.linear;
+        assert!((velocity.y + 9.81 / 2.0).abs() < 1.0e-2);
+    }
+
+    #[test]
+    fn carried_by_wind() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity exposed to wind along the x axis
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(DragForce::<f32> { coefficient: 2.0 })
+            .with(WindForce::<f32> {
+                velocity: Vector3::new(3.0, 0.0, 0.0),
+            })
+            .build();
+        for _ in 0..300 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the Entity ends up moving along with the wind
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let velocity = physics_bodies.get(entity).unwrap().velocity.linear;
+        assert!((velocity - Vector3::new(3.0, 0.0, 0.0)).norm() < 1.0e-2);
+    }
+
+    #[test]
+    fn spring_towards_rest_length() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create two Entities connected by a stretched, damped spring
+        let mut create_body = |x| {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(x, 0.0, 0.0)))
+                .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+                .build()
+        };
+        let entity1 = create_body(0.0);
+        let entity2 = create_body(3.0);
+        world
+            .write_storage::<SpringForce<f32>>()
+            .insert(
+                entity1,
+                SpringForce::new(entity2, 1.0, 10.0).with_damping(2.0),
+            )
+            .unwrap();
+
+        let distance = |world: &World| {
+            let positions = world.read_storage::<SimplePosition<f32>>();
+            let translation = |entity| positions.get(entity).unwrap().0.translation.vector;
+            (translation(entity2) - translation(entity1)).norm()
+        };
+
+        // the bodies are pulled towards each other first
+        dispatcher.dispatch(&world);
+        assert!(distance(&world) < 3.0);
+
+        // and eventually come to rest at the rest length
+        for _ in 0..600 {
+            dispatcher.dispatch(&world);
+        }
+        assert!((distance(&world) - 1.0).abs() < 1.0e-2);
+
+        // deleting an endpoint leaves the other body alone
+        world.delete_entity(entity2).unwrap();
+        world.maintain();
+        dispatcher.dispatch(&world);
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        assert!(physics_bodies.get(entity1).unwrap().velocity.linear.norm() < 1.0e-2);
+    }
+
+    #[test]
+    fn attract_towards_center() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Attractor without a PhysicsBody and a body passing by it
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(Attractor::<f32> {
+                strength: 10.0,
+                falloff: Falloff::InverseSquare,
+                min_distance: 0.5,
+            })
+            .build();
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(2.0, 0.0, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(0.0, 1.0, 0.0))
+                    .build(),
+            )
+            .build();
+        for _ in 0..10 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the velocity of the body curves towards the Attractor
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let velocity = physics_bodies.get(entity).unwrap().velocity.linear;
+        assert!(velocity.x < 0.0);
+        assert!(velocity.y > 0.9);
+    }
+
+    #[test]
+    fn no_forces_while_paused() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity exposed to wind along the x axis and pause the
+        // simulation for a while
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(DragForce::<f32> { coefficient: 2.0 })
+            .with(WindForce::<f32> {
+                velocity: Vector3::new(3.0, 0.0, 0.0),
+            })
+            .build();
+        world.insert(PhysicsPaused(true));
+        for _ in 0..60 {
+            dispatcher.dispatch(&world);
+        }
+
+        // after resuming, only the force of a single dispatch has been applied
+        world.insert(PhysicsPaused(false));
+        dispatcher.dispatch(&world);
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let velocity = physics_bodies.get(entity).unwrap().velocity.linear;
+        assert!(velocity.x > 0.0 && velocity.x < 0.2);
+    }
+
+    #[test]
+    fn sideways_body_gravity() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity whose own gravity points along the x axis
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .with(BodyGravity::<f32>(Vector3::new(2.0, 0.0, 0.0)))
+            .build();
+        for _ in 0..60 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the Entity drifts horizontally and ignores the world gravity
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        let translation = positions.get(entity).unwrap().0.translation.vector;
+        assert!(translation.x > 0.5);
+        assert!(translation.y.abs() < 1.0e-3);
+
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let physics_body = physics_bodies.get(entity).unwrap();
+        assert!((physics_body.velocity.linear.x - 2.0).abs() < 0.1);
+        assert!(!physics_body.gravity_enabled);
+    }
+
+    #[test]
+    fn remove_body_gravity() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity whose own gravity is disabled
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .with(BodyGravity::<f32>(Vector3::zeros()))
+            .build();
+        for _ in 0..10 {
+            dispatcher.dispatch(&world);
+        }
+        assert_eq!(
+            world
+                .read_storage::<SimplePosition<f32>>()
+                .get(entity)
+                .unwrap()
+                .0,
+            Isometry3::identity()
+        );
+
+        // removing the BodyGravity lets the Entity fall with the world gravity
+        world.write_storage::<BodyGravity<f32>>().remove(entity);
+        for _ in 0..10 {
+            dispatcher.dispatch(&world);
+        }
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        assert!(positions.get(entity).unwrap().0.translation.vector.y < 0.0);
+
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        assert!(physics_bodies.get(entity).unwrap().gravity_enabled);
+    }
+}
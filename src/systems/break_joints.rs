@@ -0,0 +1,147 @@
+use std::marker::PhantomData;
+
+use specs::{Entities, ReadExpect, System, SystemData, World, Write, WriteStorage};
+
+use crate::{
+    events::{JointBroken, JointBrokenEvents},
+    joints::PhysicsJoint,
+    nalgebra::RealField,
+    nphysics::joint::JointConstraint,
+    Physics,
+};
+
+/// The `BreakJointsSystem` removes the `PhysicsJoint`s whose joint constraint
+/// broke during the last simulation step, as the reaction force or torque
+/// exceeded its `break_force` or `break_torque`. The removal follows the
+/// regular removal path of the `SyncJointsToPhysicsSystem` and emits a
+/// `JointBroken` event. It has to run after the `PhysicsStepperSystem`.
+pub struct BreakJointsSystem<N> {
+    n_marker: PhantomData<N>,
+}
+
+impl<'s, N: RealField> System<'s> for BreakJointsSystem<N> {
+    type SystemData = (
+        Entities<'s>,
+        ReadExpect<'s, Physics<N>>,
+        WriteStorage<'s, PhysicsJoint<N>>,
+        Write<'s, JointBrokenEvents>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, physics, mut physics_joints, mut joint_broken_events) = data;
+
+        // broken joint constraints are ignored by nphysics but remain in the world
+        // until their PhysicsJoint is removed; the ids are sorted to emit the events
+        // in a deterministic order
+        let mut broken_ids = physics
+            .joint_handles
+            .iter()
+            .filter(|(_, handle)| {
+                physics
+                    .joint_constraints
+                    .get(**handle)
+                    .map_or(false, |joint_constraint| joint_constraint.is_broken())
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        broken_ids.sort();
+
+        for id in broken_ids {
+            let entity = entities.entity(id);
+            if physics_joints.remove(entity).is_some() {
+                debug!("Broke PhysicsJoint with id: {}", id);
+                joint_broken_events.single_write(JointBroken(entity));
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("BreakJointsSystem.setup");
+        Self::SystemData::setup(res);
+
+        // initialise required resources
+        res.entry::<Physics<N>>().or_insert_with(Physics::default);
+    }
+}
+
+impl<N> Default for BreakJointsSystem<N>
+where
+    N: RealField,
+{
+    fn default() -> Self {
+        Self {
+            n_marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        all_physics_dispatcher,
+        colliders::Shape,
+        events::{JointBroken, JointBrokenEvents},
+        joints::{BallJoint, JointType, PhysicsJoint},
+        nalgebra::{Isometry3, Point3, Vector3},
+        nphysics::object::BodyStatus,
+        parameters::Gravity,
+        EntityBuilderExt,
+        Physics,
+        PhysicsBodyBuilder,
+        PhysicsColliderBuilder,
+        PhysicsJointBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn break_joint() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+        let mut reader_id = world
+            .write_resource::<JointBrokenEvents>()
+            .register_reader();
+
+        // hang a heavy ball from the ground with a joint too weak to carry it
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 5.0, 0.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 })
+                    .density(100.0)
+                    .build(),
+            )
+            .with_joint(
+                PhysicsJointBuilder::<f32>::from(JointType::Ball(BallJoint {
+                    local_anchor_1: Point3::new(0.0, 1.0, 0.0),
+                    local_anchor_2: Point3::new(0.0, 6.0, 0.0),
+                }))
+                .break_force(1.0)
+                .build(),
+            )
+            .build();
+        for _ in 0..3 {
+            dispatcher.dispatch(&world);
+            world.maintain();
+        }
+
+        // the PhysicsJoint is gone along with its joint constraint
+        assert!(world
+            .read_storage::<PhysicsJoint<f32>>()
+            .get(entity)
+            .is_none());
+        assert!(world
+            .read_resource::<Physics<f32>>()
+            .joint_handles
+            .get(&entity.id())
+            .is_none());
+
+        let joint_broken_events = world.read_resource::<JointBrokenEvents>();
+        let events = joint_broken_events.read(&mut reader_id).collect::<Vec<_>>();
+        assert_eq!(events, vec![&JointBroken(entity)]);
+    }
+}
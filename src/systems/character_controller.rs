@@ -0,0 +1,447 @@
+use std::marker::PhantomData;
+
+use specs::{
+    world::EntitiesRes,
+    Entities,
+    Entity,
+    Join,
+    Read,
+    System,
+    SystemData,
+    World,
+    WriteExpect,
+    WriteStorage,
+};
+
+use crate::{
+    character::CharacterController,
+    nalgebra::{self as na, Isometry3, Point3, RealField, Vector3},
+    ncollide::{
+        pipeline::CollisionGroups,
+        query::TOIStatus,
+        shape::{Capsule, ShapeHandle},
+    },
+    nphysics::{
+        algebra::Velocity3,
+        object::{Body, BodyStatus},
+    },
+    parameters::{is_stepping, PhysicsPaused, StepOnce, TimeStep},
+    Physics,
+};
+
+/// The number of surfaces the movement of a character can be deflected by
+/// within a single step.
+const MAX_SLIDES: usize = 4;
+
+/// The `CharacterControllerSystem` moves the kinematic `RigidBody`s of all
+/// `Entity`s with a `CharacterController` and updates their ground. It has to
+/// run before the `PhysicsStepperSystem`, which moves the bodies by the
+/// velocity computed here. While the simulation is paused, the characters are
+/// left untouched.
+///
+/// The capsules of the characters are swept against all solid
+/// `PhysicsCollider`s except the `Entity`s own. Characters keep a small gap to
+/// the surfaces they touch and are not pushed out of colliders they already
+/// overlap. Characters without a kinematic `RigidBody` are skipped and lose
+/// their ground.
+pub struct CharacterControllerSystem<N> {
+    n_marker: PhantomData<N>,
+}
+
+impl<'s, N: RealField> System<'s> for CharacterControllerSystem<N> {
+    type SystemData = (
+        Entities<'s>,
+        Option<Read<'s, TimeStep<N>>>,
+        Option<Read<'s, PhysicsPaused>>,
+        Option<Read<'s, StepOnce>>,
+        WriteStorage<'s, CharacterController<N>>,
+        WriteExpect<'s, Physics<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, time_step, paused, step_once, mut character_controllers, mut physics) = data;
+
+        // kinematic bodies keep moving with their velocity, so characters are only
+        // moved in dispatches that actually perform a step
+        if !is_stepping(paused.as_deref(), step_once.as_deref()) {
+            return;
+        }
+
+        // the PhysicsStepperSystem applies the TimeStep right after this System
+        let timestep = time_step.map_or_else(|| physics.timestep(), |time_step| time_step.0);
+        if timestep <= N::zero() {
+            return;
+        }
+        let gravity = physics.gravity().y;
+
+        for (entity, character_controller) in (&entities, &mut character_controllers).join() {
+            let handle = match physics.body_handles.get(&entity.id()) {
+                Some(handle) => *handle,
+                None => {
+                    character_controller.ground = None;
+                    continue;
+                }
+            };
+            let start = match physics.bodies.rigid_body(handle) {
+                Some(rigid_body) if rigid_body.status() == BodyStatus::Kinematic => {
+                    Point3::from(rigid_body.position().translation.vector)
+                }
+                _ => {
+                    character_controller.ground = None;
+                    continue;
+                }
+            };
+
+            let sweep = CapsuleSweep {
+                physics: &physics,
+                entities: &entities,
+                shape: ShapeHandle::new(Capsule::new(
+                    character_controller.half_height,
+                    character_controller.radius,
+                )),
+                exclude: [entity],
+                min_normal_y: character_controller.max_slope.cos(),
+                skin: na::convert(0.01),
+            };
+            let grounded = character_controller.is_grounded()
+                && character_controller.vertical_velocity <= N::zero();
+
+            // walk along the ground; obstacles are only stepped onto from the ground
+            let movement = Vector3::new(
+                character_controller.movement.x,
+                N::zero(),
+                character_controller.movement.z,
+            ) * timestep;
+            let step_height = if grounded {
+                character_controller.step_height
+            } else {
+                N::zero()
+            };
+            let mut position = sweep.walk(&start, &movement, step_height);
+
+            if grounded {
+                // stick to the ground when walking down slopes and stairs
+                character_controller.vertical_velocity = N::zero();
+                let snap_distance = character_controller
+                    .step_height
+                    .max(movement.norm() * character_controller.max_slope.tan())
+                    + sweep.skin;
+                let (snapped, normal) = sweep.cast(&position, &(-Vector3::y() * snap_distance));
+                if normal.map_or(false, |normal| sweep.is_walkable(&normal)) {
+                    position = snapped;
+                }
+            } else {
+                character_controller.vertical_velocity += gravity * timestep;
+                let fall = Vector3::y() * (character_controller.vertical_velocity * timestep);
+                let (moved, normal) = sweep.cast(&position, &fall);
+                match normal {
+                    // slopes too steep to stand on are slid down
+                    Some(normal) if normal.y > N::zero() && !sweep.is_walkable(&normal) => {
+                        let remaining = fall - (moved - position);
+                        position = sweep
+                            .cast(&moved, &(remaining - normal * remaining.dot(&normal)))
+                            .0;
+                    }
+                    // landing on the ground or bumping the head stops the fall or jump
+                    Some(_) => {
+                        character_controller.vertical_velocity = N::zero();
+                        position = moved;
+                    }
+                    None => position = moved,
+                }
+            }
+
+            // the ray reaches the ground below the capsule on the steepest walkable
+            // slope
+            character_controller.ground = physics
+                .ground_check(
+                    &entities,
+                    &position,
+                    character_controller.half_height
+                        + character_controller.radius / sweep.min_normal_y
+                        + sweep.skin * na::convert(2.0),
+                    &CollisionGroups::new(),
+                    &[entity],
+                )
+                .filter(|ground| sweep.is_walkable(&ground.normal));
+
+            if let Some(rigid_body) = physics.bodies.rigid_body_mut(handle) {
+                rigid_body.set_velocity(Velocity3::new(
+                    (position - start) / timestep,
+                    Vector3::zeros(),
+                ));
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("CharacterControllerSystem.setup");
+        Self::SystemData::setup(res);
+
+        // initialise required resources
+        res.entry::<Physics<N>>().or_insert_with(Physics::default);
+    }
+}
+
+impl<N> Default for CharacterControllerSystem<N>
+where
+    N: RealField,
+{
+    fn default() -> Self {
+        Self {
+            n_marker: PhantomData,
+        }
+    }
+}
+
+/// Sweeps the upright capsule of a single character through the physics
+/// world.
+struct CapsuleSweep<'a, N: RealField> {
+    physics: &'a Physics<N>,
+    entities: &'a EntitiesRes,
+    shape: ShapeHandle<N>,
+    exclude: [Entity; 1],
+    // the cosine of the max_slope; the normals of walkable surfaces have at
+    // least this y component
+    min_normal_y: N,
+    // the gap kept to the surfaces hit
+    skin: N,
+}
+
+impl<'a, N: RealField> CapsuleSweep<'a, N> {
+    fn is_walkable(&self, normal: &Vector3<N>) -> bool {
+        normal.y >= self.min_normal_y
+    }
+
+    /// Moves the capsule from `from` by `displacement` until it hits a surface.
+    /// Returns the reached position and the normal of the surface hit, pointing
+    /// towards the capsule.
+    fn cast(&self, from: &Point3<N>, displacement: &Vector3<N>) -> (Point3<N>, Option<Vector3<N>>) {
+        let length = displacement.norm();
+        if length <= N::default_epsilon() {
+            return (*from, None);
+        }
+
+        let direction = displacement / length;
+        match self.physics.shape_cast(
+            self.entities,
+            &self.shape,
+            &Isometry3::translation(from.x, from.y, from.z),
+            &direction,
+            length,
+            &CollisionGroups::new(),
+            &self.exclude,
+        ) {
+            Some((_, toi)) => {
+                // the capsule isn't rotated, so its local normal is in world space;
+                // overlapped colliders have no normal and block like a wall
+                let normal = if toi.status == TOIStatus::Penetrating {
+                    -direction
+                } else {
+                    -toi.normal1.into_inner()
+                };
+                let travel = (toi.toi - self.skin).max(N::zero());
+                (from + direction * travel, Some(normal))
+            }
+            None => (from + displacement, None),
+        }
+    }
+
+    /// Moves the capsule from `from` by `displacement`, sliding along the
+    /// surfaces it hits. Walkable surfaces deflect the movement along their
+    /// slope, whereas steeper ones only deflect it sideways, so they block the
+    /// character like walls unless they can be stepped onto.
+    fn walk(&self, from: &Point3<N>, displacement: &Vector3<N>, step_height: N) -> Point3<N> {
+        let mut position = *from;
+        let mut remaining = *displacement;
+        for _ in 0..MAX_SLIDES {
+            let (reached, normal) = self.cast(&position, &remaining);
+            remaining -= reached - position;
+            position = reached;
+
+            let mut normal = match normal {
+                Some(normal) => normal,
+                None => break,
+            };
+            if !self.is_walkable(&normal) {
+                if let Some(stepped) = self.step_up(&position, &remaining, step_height) {
+                    return stepped;
+                }
+
+                normal.y = N::zero();
+                normal = match normal.try_normalize(N::default_epsilon()) {
+                    Some(normal) => normal,
+                    None => break,
+                };
+            }
+            remaining -= normal * remaining.dot(&normal);
+        }
+
+        position
+    }
+
+    /// Tries to move the capsule from `from` by `displacement` on top of the
+    /// obstacle blocking it. Fails if the obstacle is higher than
+    /// `step_height` or its top isn't walkable.
+    fn step_up(
+        &self,
+        from: &Point3<N>,
+        displacement: &Vector3<N>,
+        step_height: N,
+    ) -> Option<Point3<N>> {
+        if step_height <= N::zero() {
+            return None;
+        }
+
+        let (raised, _) = self.cast(from, &(Vector3::y() * step_height));
+        let (advanced, _) = self.cast(&raised, displacement);
+        if (advanced - raised).norm() <= self.skin {
+            return None;
+        }
+
+        let (landed, normal) = self.cast(
+            &advanced,
+            &(-Vector3::y() * (raised.y - from.y + self.skin)),
+        );
+        normal
+            .filter(|normal| self.is_walkable(normal))
+            .map(|_| landed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        all_physics_dispatcher,
+        character::CharacterController,
+        colliders::Shape,
+        nalgebra::{Isometry3, UnitQuaternion, Vector3},
+        nphysics::object::BodyStatus,
+        parameters::Gravity,
+        PhysicsBodyBuilder,
+        PhysicsColliderBuilder,
+        SimplePosition,
+    };
+
+    /// Creates a world with a static floor whose top is at a height of `0` and
+    /// a character standing on it at the origin, walking along the x axis.
+    fn character_on_floor() -> (World, Dispatcher<'static, 'static>, Entity) {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                0.0, -1.0, 0.0,
+            )))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(20.0, 1.0, 20.0),
+                })
+                .build(),
+            )
+            .build();
+        let character = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 1.1, 0.0)))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Kinematic).build())
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Capsule {
+                    half_height: 0.5,
+                    radius: 0.5,
+                })
+                .build(),
+            )
+            .with(
+                CharacterController::<f32>::new(0.5, 0.5)
+                    .with_step_height(0.3)
+                    .with_movement(Vector3::new(2.0, 0.0, 0.0)),
+            )
+            .build();
+
+        (world, dispatcher, character)
+    }
+
+    fn character_position(world: &World, character: Entity) -> Vector3<f32> {
+        world
+            .read_storage::<SimplePosition<f32>>()
+            .get(character)
+            .unwrap()
+            .0
+            .translation
+            .vector
+    }
+
+    #[test]
+    fn stop_at_wall() {
+        let (mut world, mut dispatcher, character) = character_on_floor();
+
+        // create a wall much higher than the step height in front of the character
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(3.0, 2.0, 0.0)))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(0.5, 2.0, 5.0),
+                })
+                .build(),
+            )
+            .build();
+        for _ in 0..180 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the character stands right in front of the wall
+        let position = character_position(&world, character);
+        assert!(position.x > 1.9 && position.x <= 2.0);
+        assert!((position.y - 1.0).abs() < 0.05);
+        assert!(world
+            .read_storage::<CharacterController<f32>>()
+            .get(character)
+            .unwrap()
+            .is_grounded());
+    }
+
+    #[test]
+    fn climb_slope() {
+        let (mut world, mut dispatcher, character) = character_on_floor();
+
+        // create a ramp rising by 20 degrees along the x axis, starting at x = 4.5
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::from_parts(
+                Vector3::new(6.0, 0.0, 0.0).into(),
+                UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 20.0f32.to_radians()),
+            )))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(4.0, 0.5, 5.0),
+                })
+                .build(),
+            )
+            .build();
+        for _ in 0..60 {
+            dispatcher.dispatch(&world);
+        }
+        let height_on_floor = character_position(&world, character).y;
+        for _ in 0..120 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the character walked up the ramp and still stands on it
+        let position = character_position(&world, character);
+        assert!(position.x > 5.0);
+        assert!(position.y > height_on_floor + 0.2);
+        let character_controllers = world.read_storage::<CharacterController<f32>>();
+        let ground = character_controllers
+            .get(character)
+            .unwrap()
+            .ground
+            .unwrap();
+        assert!((ground.normal.y - 20.0f32.to_radians().cos()).abs() < 1.0e-3);
+    }
+}
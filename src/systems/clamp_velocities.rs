@@ -0,0 +1,130 @@
+use std::marker::PhantomData;
+
+use specs::{Entities, Join, ReadStorage, System, SystemData, World, WriteExpect};
+
+use crate::{
+    bodies::MaxVelocity,
+    nalgebra::{RealField, Vector3},
+    nphysics::{algebra::Velocity3, object::RigidBody},
+    Physics,
+};
+
+/// The `ClampVelocitiesSystem` caps the velocities of `RigidBody`s with a
+/// `MaxVelocity` `Component` after every simulation step, so the clamped
+/// velocities are synchronised back into the `PhysicsBody`s.
+pub struct ClampVelocitiesSystem<N> {
+    n_marker: PhantomData<N>,
+}
+
+impl<'s, N: RealField> System<'s> for ClampVelocitiesSystem<N> {
+    type SystemData = (
+        Entities<'s>,
+        ReadStorage<'s, MaxVelocity<N>>,
+        WriteExpect<'s, Physics<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, max_velocities, mut physics) = data;
+
+        for (entity, max_velocity) in (&entities, &max_velocities).join() {
+            if let Some(rigid_body) = physics
+                .body_handles
+                .get(&entity.id())
+                .cloned()
+                .and_then(|handle| physics.bodies.rigid_body_mut(handle))
+            {
+                clamp_velocity(rigid_body, max_velocity);
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("ClampVelocitiesSystem.setup");
+        Self::SystemData::setup(res);
+
+        // initialise required resources
+        res.entry::<Physics<N>>().or_insert_with(Physics::default);
+    }
+}
+
+impl<N> Default for ClampVelocitiesSystem<N>
+where
+    N: RealField,
+{
+    fn default() -> Self {
+        Self {
+            n_marker: PhantomData,
+        }
+    }
+}
+
+fn clamp_velocity<N>(rigid_body: &mut RigidBody<N>, max_velocity: &MaxVelocity<N>)
+where
+    N: RealField,
+{
+    let velocity = rigid_body.velocity();
+    let linear = clamp_norm(velocity.linear, max_velocity.linear);
+    let angular = clamp_norm(velocity.angular, max_velocity.angular);
+    if linear != velocity.linear || angular != velocity.angular {
+        rigid_body.set_velocity(Velocity3::new(linear, angular));
+    }
+}
+
+fn clamp_norm<N>(vector: Vector3<N>, max_norm: N) -> Vector3<N>
+where
+    N: RealField,
+{
+    let norm = vector.norm();
+    if norm > max_norm {
+        vector * (max_norm / norm)
+    } else {
+        vector
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        all_physics_dispatcher,
+        bodies::MaxVelocity,
+        nalgebra::{Isometry3, Vector3},
+        nphysics::{algebra::Velocity3, object::BodyStatus},
+        PhysicsBody,
+        PhysicsBodyBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn clamp_velocity() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with a huge velocity exceeding its MaxVelocity
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::new(
+                        Vector3::new(300.0, 400.0, 0.0),
+                        Vector3::new(0.0, 0.0, 100.0),
+                    ))
+                    .build(),
+            )
+            .with(MaxVelocity::<f32> {
+                linear: 5.0,
+                angular: 1.0,
+            })
+            .build();
+        dispatcher.dispatch(&world);
+
+        // the velocity is capped while keeping its direction
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let velocity = physics_bodies.get(entity).unwrap().velocity;
+        assert!((velocity.linear - Vector3::new(3.0, 4.0, 0.0)).norm() < 1.0e-4);
+        assert!((velocity.angular - Vector3::new(0.0, 0.0, 1.0)).norm() < 1.0e-4);
+    }
+}
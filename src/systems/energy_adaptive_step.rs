@@ -0,0 +1,120 @@
+use std::marker::PhantomData;
+
+use specs::{Read, ReadExpect, System, SystemData, World, Write};
+
+use crate::{
+    nalgebra::RealField,
+    parameters::{EnergyAdaptiveStep, TimeStep},
+    Physics,
+};
+
+/// The `EnergyAdaptiveStepSystem` writes the `TimeStep` picked by the
+/// `EnergyAdaptiveStep` `Resource` for the current kinetic energy of the
+/// simulation. It has to run before the `PhysicsStepperSystem`, which applies
+/// the `TimeStep` to the next step. Without an `EnergyAdaptiveStep`, the
+/// `TimeStep` is left untouched.
+pub struct EnergyAdaptiveStepSystem<N> {
+    n_marker: PhantomData<N>,
+}
+
+impl<'s, N: RealField> System<'s> for EnergyAdaptiveStepSystem<N> {
+    type SystemData = (
+        Option<Read<'s, EnergyAdaptiveStep<N>>>,
+        ReadExpect<'s, Physics<N>>,
+        Write<'s, TimeStep<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (adaptive_step, physics, mut time_step) = data;
+
+        if let Some(adaptive_step) = adaptive_step {
+            time_step.0 = adaptive_step.step(physics.kinetic_energy());
+        }
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("EnergyAdaptiveStepSystem.setup");
+        Self::SystemData::setup(res);
+
+        // initialise required resources
+        res.entry::<Physics<N>>().or_insert_with(Physics::default);
+    }
+}
+
+impl<N> Default for EnergyAdaptiveStepSystem<N>
+where
+    N: RealField,
+{
+    fn default() -> Self {
+        Self {
+            n_marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_ulps_eq;
+    use specs::prelude::*;
+
+    use crate::{
+        all_physics_dispatcher,
+        nalgebra::Isometry3,
+        nphysics::{algebra::Velocity3, object::BodyStatus},
+        parameters::{EnergyAdaptiveStep, TimeStep},
+        Physics,
+        PhysicsBodyBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn pick_step() {
+        let adaptive_step = EnergyAdaptiveStep::<f32>::new(1.0 / 240.0, 1.0 / 60.0)
+            .with_step(1000.0, 1.0 / 1000.0)
+            .with_step(50.0, 1.0 / 120.0);
+
+        // calm scenes use the largest step, energy spikes shrink it down to the
+        // smallest one
+        assert_ulps_eq!(adaptive_step.step(0.0), 1.0 / 60.0);
+        assert_ulps_eq!(adaptive_step.step(50.0), 1.0 / 120.0);
+        assert_ulps_eq!(adaptive_step.step(10_000.0), 1.0 / 240.0);
+    }
+
+    #[test]
+    fn shrink_step_on_high_energy() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        world.insert(
+            EnergyAdaptiveStep::<f32>::new(1.0 / 240.0, 1.0 / 60.0).with_step(50.0, 1.0 / 120.0),
+        );
+
+        // a resting body keeps the largest step
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .build();
+        dispatcher.dispatch(&world);
+        assert_ulps_eq!(world.read_resource::<TimeStep<f32>>().0, 1.0 / 60.0);
+
+        // a fast body raises the kinetic energy to 0.5 * 2 * 10^2 = 100
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(5.0, 0.0, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .mass(2.0)
+                    .velocity(Velocity3::linear(10.0, 0.0, 0.0))
+                    .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+        assert!((world.read_resource::<Physics<f32>>().kinetic_energy() - 100.0).abs() < 1.0e-3);
+        assert_ulps_eq!(world.read_resource::<TimeStep<f32>>().0, 1.0 / 120.0);
+        assert_ulps_eq!(
+            world.read_resource::<Physics<f32>>().timestep(),
+            1.0 / 120.0
+        );
+    }
+}
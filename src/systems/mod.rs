@@ -10,18 +10,40 @@ use specs::{
 };
 
 pub use self::{
+    apply_forces::ApplyForcesSystem,
+    break_joints::BreakJointsSystem,
+    character_controller::CharacterControllerSystem,
+    clamp_velocities::ClampVelocitiesSystem,
+    energy_adaptive_step::EnergyAdaptiveStepSystem,
+    network_smoothing::NetworkSmoothingSystem,
     physics_stepper::PhysicsStepperSystem,
+    raycast_wheels::RaycastWheelSystem,
+    surface_velocities::SurfaceVelocitySystem,
     sync_bodies_from_physics::SyncBodiesFromPhysicsSystem,
     sync_bodies_to_physics::SyncBodiesToPhysicsSystem,
     sync_colliders_to_physics::SyncCollidersToPhysicsSystem,
+    sync_collision_layers::SyncCollisionLayersSystem,
+    sync_joints_to_physics::SyncJointsToPhysicsSystem,
     sync_parameters_to_physics::SyncParametersToPhysicsSystem,
+    trigger_events::TriggerEventsSystem,
 };
 
+mod apply_forces;
+mod break_joints;
+mod character_controller;
+mod clamp_velocities;
+mod energy_adaptive_step;
+mod network_smoothing;
 mod physics_stepper;
+mod raycast_wheels;
+mod surface_velocities;
 mod sync_bodies_from_physics;
 mod sync_bodies_to_physics;
 mod sync_colliders_to_physics;
+mod sync_collision_layers;
+mod sync_joints_to_physics;
 mod sync_parameters_to_physics;
+mod trigger_events;
 
 /// Iterated over the `ComponentEvent::Inserted`s of a given, tracked `Storage`
 /// and returns the results in a `BitSet`.
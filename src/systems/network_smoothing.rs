@@ -0,0 +1,195 @@
+use std::marker::PhantomData;
+
+use specs::{Join, Read, ReadStorage, System, SystemData, World, WriteStorage};
+
+use crate::{
+    bodies::util::{NetworkSmoothing, SimplePosition},
+    nalgebra::RealField,
+    parameters::TimeStep,
+};
+
+/// The `NetworkSmoothingSystem` eases the `SimplePosition`s of `Entity`s with a
+/// `NetworkSmoothing` `Component` towards their authoritative targets. It is
+/// not part of the default physics `Dispatcher` and is meant for `Entity`s
+/// whose pose is replicated rather than simulated locally; it should run
+/// before the `SyncBodiesToPhysicsSystem` if the `Entity`s have a
+/// `PhysicsBody`.
+///
+/// Each run advances the smoothing by the `TimeStep`, i.e. the time simulated
+/// per dispatch, so the easing speed doesn't depend on the frame rate.
+pub struct NetworkSmoothingSystem<N> {
+    n_marker: PhantomData<N>,
+}
+
+impl<'s, N: RealField> System<'s> for NetworkSmoothingSystem<N> {
+    type SystemData = (
+        Read<'s, TimeStep<N>>,
+        ReadStorage<'s, NetworkSmoothing<N>>,
+        WriteStorage<'s, SimplePosition<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (time_step, network_smoothings, mut positions) = data;
+
+        for (network_smoothing, mut position) in
+            (&network_smoothings, &mut positions.restrict_mut()).join()
+        {
+            let current = *position.get_unchecked();
+            let target = network_smoothing.target;
+            if current == target {
+                continue;
+            }
+
+            // large corrections are applied immediately instead of sliding the
+            // position across the world
+            let distance = (target.0.translation.vector - current.0.translation.vector).norm();
+            *position.get_mut_unchecked() = if distance > network_smoothing.snap_distance {
+                target
+            } else {
+                // the fraction of the remaining distance closed during this frame
+                let fraction = N::one() - (-network_smoothing.rate * time_step.0).exp();
+                current.lerp(&target, fraction)
+            };
+        }
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("NetworkSmoothingSystem.setup");
+        Self::SystemData::setup(res);
+    }
+}
+
+impl<N> Default for NetworkSmoothingSystem<N>
+where
+    N: RealField,
+{
+    fn default() -> Self {
+        Self {
+            n_marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use super::NetworkSmoothingSystem;
+    use crate::{
+        bodies::util::{NetworkSmoothing, SimplePosition},
+        nalgebra::{Isometry3, Vector3},
+        parameters::TimeStep,
+    };
+
+    #[test]
+    fn converge_to_target() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                NetworkSmoothingSystem::<f32>::default(),
+                "network_smoothing_system",
+                &[],
+            )
+            .build();
+        dispatcher.setup(&mut world);
+
+        // create an Entity whose authoritative pose is offset and rotated
+        let target = SimplePosition::<f32>(Isometry3::new(
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        ));
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(NetworkSmoothing::<f32> {
+                target,
+                rate: 30.0,
+                snap_distance: 10.0,
+            })
+            .build();
+
+        // the pose moves towards the target without snapping to it
+        dispatcher.dispatch(&world);
+        {
+            let positions = world.read_storage::<SimplePosition<f32>>();
+            let translation = positions.get(entity).unwrap().0.translation.vector;
+            assert!((translation.x - (1.0 - (-0.5f32).exp())).abs() < 1.0e-4);
+        }
+
+        // and converges over several frames
+        for _ in 0..30 {
+            dispatcher.dispatch(&world);
+        }
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        let position = positions.get(entity).unwrap();
+        assert!((position.0.translation.vector - target.0.translation.vector).norm() < 1.0e-4);
+        assert!(position.0.rotation.angle_to(&target.0.rotation) < 1.0e-3);
+    }
+
+    #[test]
+    fn snap_large_correction() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                NetworkSmoothingSystem::<f32>::default(),
+                "network_smoothing_system",
+                &[],
+            )
+            .build();
+        dispatcher.setup(&mut world);
+
+        // create an Entity whose authoritative pose exceeds the snap distance
+        let target = SimplePosition::<f32>(Isometry3::translation(50.0, 0.0, 0.0));
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(NetworkSmoothing::<f32> {
+                target,
+                rate: 5.0,
+                snap_distance: 10.0,
+            })
+            .build();
+        dispatcher.dispatch(&world);
+
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        assert_eq!(positions.get(entity).unwrap(), &target);
+    }
+
+    #[test]
+    fn independent_of_frame_rate() {
+        // smooths a correction for one second at the given frames per second
+        let smooth = |frames_per_second: u32| {
+            let mut world = World::new();
+            let mut dispatcher = DispatcherBuilder::new()
+                .with(
+                    NetworkSmoothingSystem::<f32>::default(),
+                    "network_smoothing_system",
+                    &[],
+                )
+                .build();
+            dispatcher.setup(&mut world);
+            world.insert(TimeStep(1.0 / frames_per_second as f32));
+
+            let entity = world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::identity()))
+                .with(NetworkSmoothing::<f32> {
+                    target: SimplePosition(Isometry3::translation(1.0, 0.0, 0.0)),
+                    rate: 1.0,
+                    snap_distance: 10.0,
+                })
+                .build();
+            for _ in 0..frames_per_second {
+                dispatcher.dispatch(&world);
+            }
+
+            let positions = world.read_storage::<SimplePosition<f32>>();
+            positions.get(entity).unwrap().0.translation.vector.x
+        };
+
+        // the same share of the correction is applied at 30 and 120 FPS
+        let expected = 1.0 - (-1.0f32).exp();
+        assert!((smooth(30) - expected).abs() < 1.0e-3);
+        assert!((smooth(120) - expected).abs() < 1.0e-3);
+    }
+}
@@ -1,18 +1,47 @@
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
 
-use specs::{world::Index, Entities, Entity, Read, System, SystemData, World, Write, WriteExpect};
+use specs::{
+    world::Index,
+    BitSet,
+    Entities,
+    Entity,
+    Join,
+    Read,
+    System,
+    SystemData,
+    World,
+    Write,
+    WriteExpect,
+    WriteStorage,
+};
 
 use crate::{
-    events::{ContactEvent, ContactEvents, ContactType, ProximityEvent, ProximityEvents},
-    nalgebra::RealField,
+    bodies::PreviousPosition,
+    events::{
+        BodySleepEvent,
+        BodySleepEvents,
+        ContactEvent,
+        ContactEvents,
+        ContactType,
+        ProximityEvent,
+        ProximityEvents,
+    },
+    nalgebra::{self as na, RealField},
     ncollide::pipeline::{CollisionObjectSet, ContactEvent as NContactEvent},
-    nphysics::object::{DefaultColliderHandle, DefaultColliderSet},
-    parameters::TimeStep,
+    nphysics::object::{Body, BodyStatus, DefaultColliderHandle, DefaultColliderSet},
+    parameters::{PhysicsPaused, PhysicsTiming, StepHash, StepHashEnabled, StepOnce, TimeStep},
     Physics,
 };
 
 /// The `PhysicsStepperSystem` progresses the nphysics `World`.
 pub struct PhysicsStepperSystem<N> {
+    last_time_step: Option<N>,
+    // the Entity ids of all dynamic bodies that were asleep after the last step
+    sleeping_bodies: BitSet,
+
     n_marker: PhantomData<N>,
 }
 
@@ -20,13 +49,46 @@ impl<'s, N: RealField> System<'s> for PhysicsStepperSystem<N> {
     type SystemData = (
         Entities<'s>,
         Option<Read<'s, TimeStep<N>>>,
+        Option<Read<'s, PhysicsPaused>>,
+        Option<Write<'s, StepOnce>>,
+        Option<Read<'s, StepHashEnabled>>,
+        Write<'s, StepHash>,
+        Write<'s, PhysicsTiming>,
         Write<'s, ContactEvents>,
         Write<'s, ProximityEvents>,
+        Write<'s, BodySleepEvents>,
         WriteExpect<'s, Physics<N>>,
+        WriteStorage<'s, PreviousPosition<N>>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, time_step, mut contact_events, mut proximity_events, mut physics) = data;
+        let (
+            entities,
+            time_step,
+            paused,
+            step_once,
+            step_hash_enabled,
+            mut step_hash,
+            mut timing,
+            mut contact_events,
+            mut proximity_events,
+            mut body_sleep_events,
+            mut physics,
+            mut previous_positions,
+        ) = data;
+
+        // a new frame begins with every run, even if no step is performed
+        timing.steps_this_frame = 0;
+        timing.time_this_frame = Duration::from_secs(0);
+
+        // don't progress the nphysics World at all while the simulation is paused,
+        // unless a single step was requested
+        if paused.map_or(false, |paused| paused.0) {
+            match step_once {
+                Some(mut step_once) if step_once.0 => step_once.0 = false,
+                _ => return,
+            }
+        }
 
         // Convert physics from Write to &mut pointer so rustc can correctly reason
         // about independence of &mut borrows to struct components
@@ -36,18 +98,39 @@ impl<'s, N: RealField> System<'s> for PhysicsStepperSystem<N> {
         // accordingly; this should not be required if the Systems are executed in a
         // fixed interval
         if let Some(time_step) = time_step {
-            // only update timestep if it actually differs from the current nphysics World
-            // one; keep in mind that changing the Resource will destabilize the simulation
-            if physics.mechanical_world.timestep() != time_step.0 {
-                warn!(
-                    "TimeStep and world.timestep() differ, changing worlds timestep from {} to: {:?}",
-                    physics.mechanical_world.timestep(),
-                    time_step.0
-                );
+            // only update timestep if it actually differs from the last applied one;
+            // differences below epsilon are float rounding artifacts, e.g. caused by
+            // semi-fixed stepping, and are ignored. Keep in mind that changing the
+            // Resource will destabilize the simulation
+            let changed = self.last_time_step.map_or(true, |last_time_step| {
+                (last_time_step - time_step.0).abs() > N::default_epsilon()
+            });
+            if changed {
+                if (physics.mechanical_world.timestep() - time_step.0).abs() > N::default_epsilon()
+                {
+                    warn!(
+                        "TimeStep and world.timestep() differ, changing worlds timestep from {} to: {:?}",
+                        physics.mechanical_world.timestep(),
+                        time_step.0
+                    );
+                }
                 physics.mechanical_world.set_timestep(time_step.0);
+                self.last_time_step = Some(time_step.0);
+            }
+        }
+
+        // remember the positions of all bodies before they are moved by the step
+        for (entity, previous_position) in (&entities, &mut previous_positions).join() {
+            if let Some(rigid_body) = physics
+                .body_handles
+                .get(&entity.id())
+                .and_then(|handle| physics.bodies.rigid_body(*handle))
+            {
+                previous_position.0 = *rigid_body.position();
             }
         }
 
+        let step_start = Instant::now();
         physics.mechanical_world.step(
             &mut physics.geometrical_world,
             &mut physics.bodies,
@@ -55,6 +138,29 @@ impl<'s, N: RealField> System<'s> for PhysicsStepperSystem<N> {
             &mut physics.joint_constraints,
             &mut physics.force_generators,
         );
+        timing.last_step = step_start.elapsed();
+        timing.steps_this_frame += 1;
+        timing.time_this_frame += timing.last_step;
+
+        if step_hash_enabled.map_or(false, |step_hash_enabled| step_hash_enabled.0) {
+            *step_hash = StepHash(hash_bodies(physics));
+        }
+
+        // only emit events for the bodies whose activation state changed during the
+        // step; bodies that have been removed since the last step are ignored
+        let sleeping_bodies = sleeping_bodies(physics);
+        body_sleep_events.iter_write(
+            (&sleeping_bodies & !&self.sleeping_bodies)
+                .join()
+                .map(|id| BodySleepEvent::Asleep(entities.entity(id)))
+                .chain(
+                    (&self.sleeping_bodies & !&sleeping_bodies)
+                        .join()
+                        .filter(|id| physics.body_handles.contains_key(id))
+                        .map(|id| BodySleepEvent::Awake(entities.entity(id))),
+                ),
+        );
+        self.sleeping_bodies = sleeping_bodies;
 
         // map occurred ncollide ContactEvents to a custom ContactEvent type; this
         // custom type contains data that is more relevant for Specs users than
@@ -142,11 +248,84 @@ where
 {
     fn default() -> Self {
         Self {
+            last_time_step: None,
+            sleeping_bodies: BitSet::new(),
             n_marker: PhantomData,
         }
     }
 }
 
+/// The 64 bit FNV-1a hash. Unlike the `DefaultHasher` its output is specified
+/// and thus stable across Rust releases and platforms, as long as the bytes
+/// are fed in a platform independent order.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Hashes the positions and velocities of all `RigidBody`s in the order of
+/// their `Entity` indices; the order of the body handles map is unspecified.
+/// The ids and the bit patterns of the values are hashed as little-endian
+/// bytes with FNV-1a.
+fn hash_bodies<N: RealField>(physics: &Physics<N>) -> u64 {
+    let mut body_handles = physics.body_handles.iter().collect::<Vec<_>>();
+    body_handles.sort_by_key(|(id, _)| **id);
+
+    let mut hasher = Fnv1a::new();
+    for (id, handle) in body_handles {
+        let rigid_body = match physics.bodies.rigid_body(*handle) {
+            Some(rigid_body) => rigid_body,
+            None => continue,
+        };
+        let position = rigid_body.position();
+        let velocity = rigid_body.velocity();
+
+        hasher.write(&id.to_le_bytes());
+        for value in position
+            .translation
+            .vector
+            .iter()
+            .chain(position.rotation.coords.iter())
+            .chain(velocity.linear.iter())
+            .chain(velocity.angular.iter())
+        {
+            // hash the exact bit pattern; N itself does not implement Hash
+            let bits = na::try_convert::<N, f64>(*value).map_or(0, f64::to_bits);
+            hasher.write(&bits.to_le_bytes());
+        }
+    }
+
+    hasher.0
+}
+
+/// Collects the `Entity` ids of all dynamic `RigidBody`s that are currently
+/// asleep.
+fn sleeping_bodies<N: RealField>(physics: &Physics<N>) -> BitSet {
+    physics
+        .body_handles
+        .iter()
+        .filter(|(_, handle)| {
+            physics
+                .bodies
+                .rigid_body(**handle)
+                .map_or(false, |rigid_body| {
+                    rigid_body.status() == BodyStatus::Dynamic && !rigid_body.is_active()
+                })
+        })
+        .map(|(id, _)| *id)
+        .collect()
+}
+
 fn entity_from_collision_object_handle<N: RealField>(
     entities: &Entities,
     collision_object_handle: DefaultColliderHandle,
@@ -162,3 +341,357 @@ fn entity_from_collision_object_handle<N: RealField>(
             .unwrap(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use specs::prelude::*;
+
+    use super::Fnv1a;
+    use crate::{
+        bodies::PreviousPosition,
+        colliders::Shape,
+        events::{BodySleepEvent, BodySleepEvents, ContactEvents, ContactType},
+        nalgebra::{Isometry3, Vector3},
+        nphysics::{algebra::Velocity3, object::BodyStatus},
+        parameters::{
+            Gravity,
+            PhysicsPaused,
+            PhysicsTiming,
+            StepHash,
+            StepHashEnabled,
+            StepOnce,
+            TimeStep,
+        },
+        physics_dispatcher,
+        Physics,
+        PhysicsBody,
+        PhysicsBodyBuilder,
+        PhysicsColliderBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn pause_simulation() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a moving Entity and pause the simulation
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(1.0, 0.0, 0.0))
+                    .build(),
+            )
+            .build();
+        world.insert(PhysicsPaused(true));
+        for _ in 0..5 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the Entity hasn't moved while paused
+        assert_eq!(
+            world
+                .read_storage::<SimplePosition<f32>>()
+                .get(entity)
+                .unwrap()
+                .0,
+            Isometry3::identity()
+        );
+
+        // after resuming, the Entity moves by a single timestep per dispatch
+        world.insert(PhysicsPaused(false));
+        dispatcher.dispatch(&world);
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        let x = positions.get(entity).unwrap().0.translation.vector.x;
+        assert!(x > 0.0 && x < 0.1);
+    }
+
+    #[test]
+    fn map_contact_events() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        let mut reader_id = world.write_resource::<ContactEvents>().register_reader();
+
+        // create two overlapping Entities and execute the dispatcher
+        let mut create_ball = |x| {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(x, 0.0, 0.0)))
+                .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+                .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+                .build()
+        };
+        let entity1 = create_ball(0.0);
+        let entity2 = create_ball(1.5);
+        dispatcher.dispatch(&world);
+
+        // exactly one ContactEvent was emitted, referring to both Entities
+        let contact_events = world.read_resource::<ContactEvents>();
+        let events = contact_events.read(&mut reader_id).collect::<Vec<_>>();
+        assert_eq!(events.len(), 1);
+        assert!(match events[0].contact_type {
+            ContactType::Started => true,
+            ContactType::Stopped => false,
+        });
+        let mut entities = [events[0].collider1, events[0].collider2];
+        entities.sort();
+        assert_eq!(entities, [entity1, entity2]);
+    }
+
+    #[test]
+    fn ignore_time_step_jitter() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // the TimeStep is applied on the first dispatch
+        world.insert(TimeStep::<f32>(1.0 / 60.0));
+        dispatcher.dispatch(&world);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .mechanical_world
+                .timestep(),
+            1.0 / 60.0
+        );
+
+        // rounding artifacts are ignored
+        for _ in 0..10 {
+            world.insert(TimeStep::<f32>(1.0 / 60.0 + 1.0e-8));
+            dispatcher.dispatch(&world);
+        }
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .mechanical_world
+                .timestep(),
+            1.0 / 60.0
+        );
+
+        // whereas actual changes are applied
+        world.insert(TimeStep::<f32>(1.0 / 30.0));
+        dispatcher.dispatch(&world);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .mechanical_world
+                .timestep(),
+            1.0 / 30.0
+        );
+    }
+
+    #[test]
+    fn update_previous_position() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a moving Entity and step twice
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(1.0, 0.0, 0.0))
+                    .build(),
+            )
+            .with(PreviousPosition::<f32>::default())
+            .build();
+        dispatcher.dispatch(&world);
+        let position = world
+            .read_storage::<SimplePosition<f32>>()
+            .get(entity)
+            .unwrap()
+            .0;
+        dispatcher.dispatch(&world);
+
+        // the PreviousPosition lags exactly one step behind
+        let previous_positions = world.read_storage::<PreviousPosition<f32>>();
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        assert_eq!(previous_positions.get(entity).unwrap().0, position);
+        assert!(
+            positions.get(entity).unwrap().0.translation.vector.x > position.translation.vector.x
+        );
+    }
+
+    #[test]
+    fn step_once() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a moving Entity, pause the simulation and request a single step
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(1.0, 0.0, 0.0))
+                    .build(),
+            )
+            .build();
+        world.insert(PhysicsPaused(true));
+        world.insert(StepOnce(true));
+        for _ in 0..5 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the Entity moved by exactly one timestep and the flag was reset
+        let timestep = world.read_resource::<Physics<f32>>().timestep();
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        let x = positions.get(entity).unwrap().0.translation.vector.x;
+        assert!((x - timestep).abs() < 1.0e-6);
+        assert!(!world.read_resource::<StepOnce>().0);
+    }
+
+    #[test]
+    fn fnv1a_reference_values() {
+        // the reference values of the FNV-1a specification
+        assert_eq!(Fnv1a::new().0, 0xcbf2_9ce4_8422_2325);
+        let mut hasher = Fnv1a::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.0, 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn identical_step_hash() {
+        let build_world = || {
+            let mut world = World::new();
+            let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+            dispatcher.setup(&mut world);
+            world.insert(StepHashEnabled(true));
+
+            // create a few colliding bodies
+            for i in 0..5 {
+                world
+                    .create_entity()
+                    .with(SimplePosition::<f32>(Isometry3::translation(
+                        i as f32 * 0.9,
+                        0.0,
+                        0.0,
+                    )))
+                    .with(
+                        PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                            .velocity(Velocity3::linear(1.0 - i as f32 * 0.5, 0.0, 0.0))
+                            .build(),
+                    )
+                    .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+                    .build();
+            }
+
+            (world, dispatcher)
+        };
+        let (world1, mut dispatcher1) = build_world();
+        let (world2, mut dispatcher2) = build_world();
+
+        for _ in 0..30 {
+            dispatcher1.dispatch(&world1);
+            dispatcher2.dispatch(&world2);
+        }
+
+        let step_hash = *world1.read_resource::<StepHash>();
+        assert_ne!(step_hash, StepHash::default());
+        assert_eq!(step_hash, *world2.read_resource::<StepHash>());
+    }
+
+    #[test]
+    fn measure_step_timing() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a few colliding bodies and run a single frame
+        for i in 0..5 {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::translation(
+                    i as f32 * 0.9,
+                    0.0,
+                    0.0,
+                )))
+                .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+                .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+                .build();
+        }
+        dispatcher.dispatch(&world);
+
+        let timing = *world.read_resource::<PhysicsTiming>();
+        assert_eq!(timing.steps_this_frame, 1);
+        assert!(timing.time_this_frame > Duration::from_secs(0));
+        assert_eq!(timing.time_this_frame, timing.last_step);
+
+        // no steps are performed while paused
+        world.insert(PhysicsPaused(true));
+        dispatcher.dispatch(&world);
+        let timing = *world.read_resource::<PhysicsTiming>();
+        assert_eq!(timing.steps_this_frame, 0);
+        assert_eq!(timing.time_this_frame, Duration::from_secs(0));
+    }
+
+    #[test]
+    fn body_sleep_events() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+        let mut reader_id = world.write_resource::<BodySleepEvents>().register_reader();
+
+        // create a static ground collider with a box resting on top of it
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 1.0, 10.0),
+                })
+                .build(),
+            )
+            .build();
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 1.5, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(0.5, 0.5, 0.5),
+                })
+                .build(),
+            )
+            .build();
+
+        // the box settles and falls asleep exactly once
+        for _ in 0..600 {
+            dispatcher.dispatch(&world);
+        }
+        let events = world
+            .read_resource::<BodySleepEvents>()
+            .read(&mut reader_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec![BodySleepEvent::Asleep(entity)]);
+
+        // disturbing the box wakes it up again
+        world
+            .write_storage::<PhysicsBody<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .velocity = Velocity3::linear(0.0, 5.0, 0.0);
+        dispatcher.dispatch(&world);
+        let events = world
+            .read_resource::<BodySleepEvents>()
+            .read(&mut reader_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(events, vec![BodySleepEvent::Awake(entity)]);
+    }
+}
@@ -0,0 +1,273 @@
+use std::marker::PhantomData;
+
+use specs::{Entities, Join, Read, System, SystemData, World, WriteExpect, WriteStorage};
+
+use crate::{
+    nalgebra::RealField,
+    ncollide::{pipeline::CollisionGroups, query::Ray},
+    nphysics::{
+        algebra::ForceType,
+        object::{Body, BodyStatus},
+    },
+    parameters::{is_stepping, PhysicsPaused, StepOnce},
+    vehicles::{RaycastWheel, WheelContact},
+    Physics,
+};
+
+/// The `RaycastWheelSystem` applies the suspension forces of all
+/// `RaycastWheel`s to their chassis `RigidBody`s and updates their
+/// `WheelContact`s. It has to run before the `PhysicsStepperSystem`, as the
+/// applied forces are cleared after every simulation step. While the
+/// simulation is paused, no forces are applied and the `WheelContact`s are
+/// left untouched.
+///
+/// The suspension rays ignore sensors and the `PhysicsCollider`s of both the
+/// chassis and the wheel `Entity`. Wheels whose chassis has no dynamic
+/// `RigidBody` are skipped and lose their contact.
+pub struct RaycastWheelSystem<N> {
+    n_marker: PhantomData<N>,
+}
+
+impl<'s, N: RealField> System<'s> for RaycastWheelSystem<N> {
+    type SystemData = (
+        Entities<'s>,
+        Option<Read<'s, PhysicsPaused>>,
+        Option<Read<'s, StepOnce>>,
+        WriteStorage<'s, RaycastWheel<N>>,
+        WriteExpect<'s, Physics<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, paused, step_once, mut raycast_wheels, mut physics) = data;
+
+        // nphysics only clears applied forces when stepping, so suspension forces
+        // applied while paused would be released all at once on resume
+        if !is_stepping(paused.as_deref(), step_once.as_deref()) {
+            return;
+        }
+
+        for (entity, raycast_wheel) in (&entities, &mut raycast_wheels).join() {
+            raycast_wheel.contact = None;
+
+            let handle = match physics.body_handles.get(&raycast_wheel.chassis.id()) {
+                Some(handle) => *handle,
+                None => continue,
+            };
+            let (mount_point, direction, mount_velocity) = match physics.bodies.rigid_body(handle) {
+                Some(rigid_body) if rigid_body.status() == BodyStatus::Dynamic => {
+                    let mount_point = rigid_body.position() * raycast_wheel.mount_point;
+                    let velocity = rigid_body.velocity();
+                    let mount_velocity = velocity.linear
+                        + velocity
+                            .angular
+                            .cross(&(mount_point - rigid_body.center_of_mass()));
+                    (
+                        mount_point,
+                        rigid_body.position().rotation * raycast_wheel.direction,
+                        mount_velocity,
+                    )
+                }
+                _ => continue,
+            };
+
+            // find the closest ground along the suspension
+            let ray = Ray::new(mount_point, direction.into_inner());
+            let exclude = [raycast_wheel.chassis, entity];
+            let (ground, intersection) = match physics
+                .raycast_all(
+                    &entities,
+                    &ray,
+                    raycast_wheel.rest_length,
+                    &CollisionGroups::new(),
+                    false,
+                )
+                .into_iter()
+                .find(|(hit_entity, _)| !exclude.contains(hit_entity))
+            {
+                Some(hit) => hit,
+                None => continue,
+            };
+
+            // the damped spring pushes the chassis away from the ground but never
+            // pulls it towards it
+            let compression = raycast_wheel.rest_length - intersection.toi;
+            let compression_velocity = mount_velocity.dot(&direction);
+            let suspension_force = (raycast_wheel.stiffness * compression
+                + raycast_wheel.damping * compression_velocity)
+                .max(N::zero());
+
+            if let Some(rigid_body) = physics.bodies.rigid_body_mut(handle) {
+                rigid_body.apply_force_at_point(
+                    0,
+                    &(-direction.into_inner() * suspension_force),
+                    &mount_point,
+                    ForceType::Force,
+                    true,
+                );
+            }
+
+            raycast_wheel.contact = Some(WheelContact {
+                entity: ground,
+                point: ray.point_at(intersection.toi),
+                normal: intersection.normal,
+                compression,
+                suspension_force,
+            });
+        }
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("RaycastWheelSystem.setup");
+        Self::SystemData::setup(res);
+
+        // initialise required resources
+        res.entry::<Physics<N>>().or_insert_with(Physics::default);
+    }
+}
+
+impl<N> Default for RaycastWheelSystem<N>
+where
+    N: RealField,
+{
+    fn default() -> Self {
+        Self {
+            n_marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        all_physics_dispatcher,
+        colliders::Shape,
+        nalgebra::{Isometry3, Point3, Vector3},
+        nphysics::object::BodyStatus,
+        parameters::{Gravity, PhysicsPaused},
+        vehicles::RaycastWheel,
+        PhysicsBody,
+        PhysicsBodyBuilder,
+        PhysicsColliderBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn rest_on_wheels() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+
+        // create a static ground collider with a chassis on four wheels above it
+        let ground = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 1.0, 10.0),
+                })
+                .build(),
+            )
+            .build();
+        let chassis = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                0.0, 2.25, 0.0,
+            )))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(1.0, 0.25, 2.0),
+                })
+                .build(),
+            )
+            .build();
+        let (stiffness, rest_length) = (200.0, 1.0);
+        let wheels = [(0.8, 1.5), (-0.8, 1.5), (0.8, -1.5), (-0.8, -1.5)]
+            .iter()
+            .map(|(x, z)| {
+                world
+                    .create_entity()
+                    .with(
+                        RaycastWheel::<f32>::new(chassis, Point3::new(*x, -0.25, *z), rest_length)
+                            .with_suspension(stiffness, 20.0),
+                    )
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..300 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the chassis came to rest where the suspensions carry its weight
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let physics_body = physics_bodies.get(chassis).unwrap();
+        assert!(physics_body.velocity.linear.norm() < 1.0e-2);
+        let compression = physics_body.mass * 9.81 / (4.0 * stiffness);
+
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        let height = positions.get(chassis).unwrap().0.translation.vector.y;
+        assert!((height - (2.0 + 0.25 + rest_length - compression)).abs() < 1.0e-2);
+
+        // all wheels touch the ground, the chassis itself doesn't
+        let raycast_wheels = world.read_storage::<RaycastWheel<f32>>();
+        for wheel in wheels {
+            let contact = raycast_wheels.get(wheel).unwrap().contact.unwrap();
+            assert_eq!(contact.entity, ground);
+            assert!((contact.compression - compression).abs() < 1.0e-2);
+            assert!((contact.point.y - 1.0).abs() < 1.0e-3);
+        }
+    }
+
+    #[test]
+    fn pause_suspension() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a static ground collider with a chassis on a compressed wheel and
+        // pause the simulation for a while
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(10.0, 1.0, 10.0),
+                })
+                .build(),
+            )
+            .build();
+        let chassis = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                0.0, 1.75, 0.0,
+            )))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .build();
+        world
+            .create_entity()
+            .with(
+                RaycastWheel::<f32>::new(chassis, Point3::new(0.0, -0.25, 0.0), 1.0)
+                    .with_suspension(200.0, 0.0),
+            )
+            .build();
+        world.insert(PhysicsPaused(true));
+        for _ in 0..60 {
+            dispatcher.dispatch(&world);
+        }
+
+        // after resuming, only the suspension force of a single dispatch has been
+        // applied
+        world.insert(PhysicsPaused(false));
+        dispatcher.dispatch(&world);
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let velocity = physics_bodies.get(chassis).unwrap().velocity.linear;
+        assert!(velocity.y > 0.0 && velocity.y < 2.0);
+    }
+}
@@ -0,0 +1,352 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use specs::{Entities, Join, Read, ReadStorage, System, SystemData, World, WriteExpect};
+
+use crate::{
+    colliders::SurfaceVelocity,
+    nalgebra::{self as na, RealField, Vector3},
+    nphysics::{
+        algebra::Velocity3,
+        material::BasicMaterial,
+        object::{Body, BodyStatus, DefaultBodyHandle},
+    },
+    parameters::PhysicsTiming,
+    Physics,
+    PhysicsCollider,
+};
+
+/// The `SurfaceVelocitySystem` carries bodies along the `PhysicsCollider`s of
+/// `Entity`s with a `SurfaceVelocity` `Component` after every simulation step.
+///
+/// For every contact with such a collider, the velocity of the touching
+/// `RigidBody` along the contact tangent is nudged towards the surface
+/// velocity, whereas its velocity along the contact normal is kept. The nudge
+/// is limited by the friction of the surface: per step, the tangential
+/// velocity changes by at most `2 * friction * g * dt`, where `g` is the
+/// gravity along the contact normal. Half of it compensates the friction
+/// nphysics applies against the static collider, the other half accelerates
+/// the body like a moving surface would. Resting bodies thus speed up to the
+/// surface velocity, sliding bodies and bodies driven by their own forces are
+/// only dragged along, and bodies touching several surfaces are nudged by the
+/// average of them. Surfaces without gravity along their normal, such as
+/// walls, don't carry bodies along.
+///
+/// The friction is taken from the `BasicMaterial` of the surface's
+/// `PhysicsCollider`; surfaces with other materials are ignored.
+///
+/// Bodies are only carried along in dispatches in which the
+/// `PhysicsStepperSystem` performed a step, so they are left alone while the
+/// simulation is paused.
+pub struct SurfaceVelocitySystem<N> {
+    n_marker: PhantomData<N>,
+}
+
+impl<'s, N: RealField> System<'s> for SurfaceVelocitySystem<N> {
+    type SystemData = (
+        Entities<'s>,
+        Read<'s, PhysicsTiming>,
+        ReadStorage<'s, SurfaceVelocity<N>>,
+        ReadStorage<'s, PhysicsCollider<N>>,
+        WriteExpect<'s, Physics<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, timing, surface_velocities, physics_colliders, mut physics) = data;
+
+        // the PhysicsStepperSystem has already consumed a potential StepOnce, so
+        // whether the simulation progressed is taken from the PhysicsTiming
+        if timing.steps_this_frame == 0 {
+            return;
+        }
+
+        // collect the velocity changes of the touching bodies first, as the
+        // contacts borrow the colliders while the velocities of the bodies are
+        // modified
+        let timestep = physics.timestep();
+        let mut nudges: HashMap<DefaultBodyHandle, (Vector3<N>, usize)> = HashMap::new();
+        for (entity, surface_velocity, physics_collider) in
+            (&entities, &surface_velocities, &physics_colliders).join()
+        {
+            let friction = match physics_collider.material.downcast_ref::<BasicMaterial<N>>() {
+                Some(material) => material.friction,
+                None => continue,
+            };
+            let handle = match physics.collider_handles.get(&entity.id()) {
+                Some(handle) => *handle,
+                None => continue,
+            };
+            let collider = match physics.colliders.get(handle) {
+                Some(collider) => collider,
+                None => continue,
+            };
+            let velocity = collider.position().rotation * surface_velocity.0;
+
+            let contacts =
+                match physics
+                    .geometrical_world
+                    .contacts_with(&physics.colliders, handle, true)
+                {
+                    Some(contacts) => contacts,
+                    None => continue,
+                };
+            for (handle1, collider1, _, collider2, _, manifold) in contacts {
+                let other = if handle1 == handle {
+                    collider2
+                } else {
+                    collider1
+                };
+                let body_velocity = match physics.bodies.rigid_body(other.body()) {
+                    Some(rigid_body) if rigid_body.status() == BodyStatus::Dynamic => {
+                        rigid_body.velocity().linear
+                    }
+                    _ => continue,
+                };
+                // the direction of the normal is irrelevant for the tangential
+                // projection
+                let normal = match manifold.deepest_contact() {
+                    Some(contact) => contact.contact.normal.into_inner(),
+                    None => continue,
+                };
+
+                // nudge the tangential velocity of the body towards the surface
+                // velocity, limited by the friction of the surface
+                let max_nudge =
+                    friction * physics.gravity().dot(&normal).abs() * timestep * na::convert(2.0);
+                let difference = velocity - body_velocity;
+                let tangential_difference = difference - normal * normal.dot(&difference);
+                let norm = tangential_difference.norm();
+                let nudge = if norm > max_nudge {
+                    tangential_difference * (max_nudge / norm)
+                } else {
+                    tangential_difference
+                };
+
+                let entry = nudges.entry(other.body()).or_insert((Vector3::zeros(), 0));
+                entry.0 += nudge;
+                entry.1 += 1;
+            }
+        }
+
+        for (body_handle, (nudge, contacts)) in nudges {
+            if nudge == Vector3::zeros() {
+                continue;
+            }
+            if let Some(rigid_body) = physics.bodies.rigid_body_mut(body_handle) {
+                let velocity = *rigid_body.velocity();
+                let contacts: N = na::convert(contacts as f64);
+                let linear = velocity.linear + nudge / contacts;
+                rigid_body.set_velocity(Velocity3::new(linear, velocity.angular));
+                rigid_body.activate();
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("SurfaceVelocitySystem.setup");
+        Self::SystemData::setup(res);
+
+        // initialise required resources
+        res.entry::<Physics<N>>().or_insert_with(Physics::default);
+    }
+}
+
+impl<N> Default for SurfaceVelocitySystem<N>
+where
+    N: RealField,
+{
+    fn default() -> Self {
+        Self {
+            n_marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        all_physics_dispatcher,
+        colliders::{Shape, SurfaceVelocity},
+        nalgebra::{Isometry3, Vector3},
+        nphysics::{
+            algebra::Velocity3,
+            object::{Body, BodyStatus},
+        },
+        parameters::{Gravity, PhysicsPaused},
+        Physics,
+        PhysicsBody,
+        PhysicsBodyBuilder,
+        PhysicsColliderBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn carry_box_on_belt() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+
+        // create a static belt moving along the x axis with a box resting on top
+        let belt = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(20.0, 1.0, 20.0),
+                })
+                .build(),
+            )
+            .with(SurfaceVelocity(Vector3::new(2.0, 0.0, 0.0)))
+            .build();
+        let cube = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 1.5, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(0.5, 0.5, 0.5),
+                })
+                .build(),
+            )
+            .build();
+        for _ in 0..120 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the box drifted in the belt direction while the belt stayed in place
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        let translation = positions.get(cube).unwrap().0.translation.vector;
+        assert!(translation.x > 1.0);
+        assert!(translation.y > 1.0 && translation.y < 2.0);
+        assert!(translation.z.abs() < 0.1);
+        assert_eq!(positions.get(belt).unwrap().0, Isometry3::identity());
+
+        let velocity = world
+            .read_storage::<PhysicsBody<f32>>()
+            .get(cube)
+            .unwrap()
+            .velocity;
+        assert!(velocity.linear.x > 1.0);
+    }
+
+    #[test]
+    fn slide_against_belt() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+
+        // create a static belt moving along the x axis with a box sliding on top
+        // against the belt direction
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(20.0, 1.0, 20.0),
+                })
+                .build(),
+            )
+            .with(SurfaceVelocity(Vector3::new(2.0, 0.0, 0.0)))
+            .build();
+        let cube = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 1.5, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .velocity(Velocity3::linear(-3.0, 0.0, 0.0))
+                    .build(),
+            )
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(0.5, 0.5, 0.5),
+                })
+                .build(),
+            )
+            .build();
+        let velocity = |world: &World| {
+            world
+                .read_storage::<PhysicsBody<f32>>()
+                .get(cube)
+                .unwrap()
+                .velocity
+                .linear
+        };
+
+        // the belt only drags the box towards its velocity instead of replacing
+        // the velocity of the box at once
+        for _ in 0..3 {
+            dispatcher.dispatch(&world);
+        }
+        assert!(velocity(&world).x < 0.0);
+
+        // until the box is eventually carried along in the belt direction
+        for _ in 0..120 {
+            dispatcher.dispatch(&world);
+        }
+        assert!(velocity(&world).x > 1.0);
+    }
+
+    #[test]
+    fn pause_belt() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+
+        // create a static belt moving along the x axis with a box resting on top
+        // and step once to detect their contact
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(20.0, 1.0, 20.0),
+                })
+                .build(),
+            )
+            .with(SurfaceVelocity(Vector3::new(2.0, 0.0, 0.0)))
+            .build();
+        let cube = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(0.0, 1.5, 0.0)))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(0.5, 0.5, 0.5),
+                })
+                .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+
+        // stop the box and pause the simulation
+        {
+            let mut physics = world.write_resource::<Physics<f32>>();
+            let handle = *physics.body_handles.get(&cube.id()).unwrap();
+            let rigid_body = physics.bodies.rigid_body_mut(handle).unwrap();
+            rigid_body.set_velocity(Velocity3::zero());
+        }
+        world.insert(PhysicsPaused(true));
+        for _ in 0..5 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the belt didn't carry the box along while paused
+        let physics = world.read_resource::<Physics<f32>>();
+        let handle = physics.body_handles.get(&cube.id()).unwrap();
+        let rigid_body = physics.bodies.rigid_body(*handle).unwrap();
+        assert_eq!(rigid_body.velocity().linear, Vector3::zeros());
+    }
+}
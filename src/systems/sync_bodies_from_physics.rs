@@ -1,16 +1,23 @@
 use std::marker::PhantomData;
 
-use specs::{Join, ReadExpect, System, SystemData, World, WriteStorage};
+use specs::{Join, ReadExpect, ReadStorage, System, SystemData, World, WriteStorage};
 
 use crate::{
-    bodies::{PhysicsBody, Position},
+    bodies::{PhysicsBody, Position, PositionSyncDisabled},
     nalgebra::RealField,
     Physics,
 };
 
 /// The `SyncBodiesFromPhysicsSystem` synchronised the updated position of
 /// the `RigidBody`s in the nphysics `World` with their Specs counterparts. This
-/// affects the `Position` `Component` related to the `Entity`.
+/// affects the `Position` `Component` related to the `Entity`. The current
+/// linear and angular velocity of each `RigidBody` is copied into the
+/// `velocity` field of its `PhysicsBody`. Entities marked with
+/// `PositionSyncDisabled` are skipped.
+///
+/// The synchronisation runs sequentially: both `Component`s live in a
+/// `FlaggedStorage`, which emits a `ComponentEvent` into a single channel for
+/// every write and thus can't be written to from multiple threads.
 pub struct SyncBodiesFromPhysicsSystem<N, P> {
     n_marker: PhantomData<N>,
     p_marker: PhantomData<P>,
@@ -25,16 +32,23 @@ where
         ReadExpect<'s, Physics<N>>,
         WriteStorage<'s, PhysicsBody<N>>,
         WriteStorage<'s, P>,
+        ReadStorage<'s, PositionSyncDisabled>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (physics, mut physics_bodies, mut positions) = data;
+        let (physics, mut physics_bodies, mut positions, sync_disabled) = data;
 
-        // iterate over all PhysicBody components joined with their Positions
-        for (physics_body, position) in (&mut physics_bodies, &mut positions).join() {
+        // iterate over all PhysicBody components joined with their Positions, skipping
+        // Entities that opted out of the synchronisation
+        for (physics_body, position, _) in
+            (&mut physics_bodies, &mut positions, !&sync_disabled).join()
+        {
             // if a RigidBody exists in the nphysics World we fetch it and update the
             // Position component accordingly
-            if let Some(rigid_body) = physics.bodies.rigid_body(physics_body.handle.unwrap()) {
+            if let Some(rigid_body) = physics_body
+                .handle
+                .and_then(|handle| physics.bodies.rigid_body(handle))
+            {
                 position.set_isometry(rigid_body.position());
                 physics_body.update_from_physics_world(rigid_body);
             }
@@ -62,3 +76,116 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        nalgebra::{Isometry3, Vector3},
+        nphysics::{algebra::Velocity3, object::BodyStatus},
+        parameters::Gravity,
+        physics_dispatcher,
+        Physics,
+        PhysicsBody,
+        PhysicsBodyBuilder,
+        PositionSyncDisabled,
+        SimplePosition,
+    };
+
+    #[test]
+    fn sync_velocity() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        world.insert(Gravity(Vector3::<f32>::new(0.0, -9.81, 0.0)));
+
+        // create a falling Entity and step the simulation a few times
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .build();
+        for _ in 0..5 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the PhysicsBody reflects the downward velocity of the RigidBody
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let velocity = physics_bodies.get(entity).unwrap().velocity;
+        assert!(velocity.linear.y < 0.0);
+        assert_eq!(velocity.linear.x, 0.0);
+        assert_eq!(velocity.linear.z, 0.0);
+    }
+
+    #[test]
+    fn skip_disabled_sync() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create two moving Entities, one of them opting out of the synchronisation
+        let physics_body = PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+            .velocity(Velocity3::linear(1.0, 0.0, 0.0))
+            .build();
+        let synced = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(physics_body)
+            .build();
+        let disabled = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(physics_body)
+            .with(PositionSyncDisabled)
+            .build();
+        for _ in 0..5 {
+            dispatcher.dispatch(&world);
+        }
+
+        // only the unmarked Entity's Position has moved
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        assert!(positions.get(synced).unwrap().0.translation.vector.x > 0.0);
+        assert_eq!(positions.get(disabled).unwrap().0, Isometry3::identity());
+    }
+
+    // steps 50k bodies through nphysics, run it with `cargo test -- --ignored`;
+    // benches/sync_bodies.rs measures the same setup
+    #[test]
+    #[ignore]
+    fn sync_many_positions() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a large amount of Entities moving with individual velocities
+        let entities = (0..50_000)
+            .map(|i| {
+                world
+                    .create_entity()
+                    .with(SimplePosition::<f32>(Isometry3::identity()))
+                    .with(
+                        PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                            .velocity(Velocity3::linear(i as f32, 0.0, 0.0))
+                            .build(),
+                    )
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        dispatcher.dispatch(&world);
+
+        // every Position received the position of its own RigidBody
+        let physics = world.read_resource::<Physics<f32>>();
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        for entity in entities {
+            let handle = physics_bodies.get(entity).unwrap().handle.unwrap();
+            let rigid_body = physics.bodies.rigid_body(handle).unwrap();
+            assert_eq!(positions.get(entity).unwrap().0, *rigid_body.position());
+        }
+    }
+}
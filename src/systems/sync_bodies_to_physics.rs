@@ -5,6 +5,7 @@ use specs::{
     world::Index,
     BitSet,
     Join,
+    Read,
     ReadStorage,
     ReaderId,
     System,
@@ -17,6 +18,7 @@ use specs::{
 use crate::{
     bodies::{PhysicsBody, Position},
     nalgebra::RealField,
+    parameters::PhysicsLogVerbosity,
     Physics,
 };
 
@@ -39,12 +41,14 @@ where
 {
     type SystemData = (
         ReadStorage<'s, P>,
+        Option<Read<'s, PhysicsLogVerbosity>>,
         WriteExpect<'s, Physics<N>>,
         WriteStorage<'s, PhysicsBody<N>>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (positions, mut physics, mut physics_bodies) = data;
+        let (positions, verbosity, mut physics, mut physics_bodies) = data;
+        let verbosity = verbosity.map_or(PhysicsLogVerbosity::default(), |verbosity| *verbosity);
 
         // collect all ComponentEvents for the Position storage
         let (inserted_positions, modified_positions, removed_positions) =
@@ -79,7 +83,7 @@ where
             // handle inserted events
             if inserted_positions.contains(id) || inserted_physics_bodies.contains(id) {
                 debug!("Inserted PhysicsBody with id: {}", id);
-                add_rigid_body::<N, P>(id, &position, &mut physics, &mut physics_body);
+                add_rigid_body::<N, P>(id, &position, &mut physics, &mut physics_body, verbosity);
             }
 
             // handle modified events
@@ -92,6 +96,7 @@ where
                     &mut physics_body,
                     &modified_positions,
                     &modified_physics_bodies,
+                    verbosity,
                 );
             }
         }
@@ -134,6 +139,7 @@ fn add_rigid_body<N, P>(
     position: &P,
     physics: &mut Physics<N>,
     physics_body: &mut PhysicsBody<N>,
+    verbosity: PhysicsLogVerbosity,
 ) where
     N: RealField,
     P: Position<N>,
@@ -159,10 +165,12 @@ fn add_rigid_body<N, P>(
     physics_body.handle = Some(handle);
     physics.body_handles.insert(id, handle);
 
-    info!(
-        "Inserted rigid body to world with values: {:?}",
-        physics_body
-    );
+    if verbosity == PhysicsLogVerbosity::Verbose {
+        trace!(
+            "Inserted rigid body to world with values: {:?}",
+            physics_body
+        );
+    }
 }
 
 fn update_rigid_body<N, P>(
@@ -172,6 +180,7 @@ fn update_rigid_body<N, P>(
     physics_body: &mut PhysicsBody<N>,
     modified_positions: &BitSet,
     modified_physics_bodies: &BitSet,
+    verbosity: PhysicsLogVerbosity,
 ) where
     N: RealField,
     P: Position<N>,
@@ -182,15 +191,20 @@ fn update_rigid_body<N, P>(
             physics_body.apply_to_physics_world(rigid_body);
         }
 
-        // the Position was modified, update the position directly
-        if modified_positions.contains(id) {
+        // the Position was modified, update the position directly; the
+        // SyncBodiesFromPhysicsSystem flags every synchronised Position as modified, so
+        // only positions that actually differ from the RigidBody's are applied to avoid
+        // feeding our own writes back into the physics world
+        if modified_positions.contains(id) && rigid_body.position() != position.isometry() {
             rigid_body.set_position(*position.isometry());
         }
 
-        trace!(
-            "Updated rigid body in world with values: {:?}",
-            physics_body
-        );
+        if verbosity == PhysicsLogVerbosity::Verbose {
+            trace!(
+                "Updated rigid body in world with values: {:?}",
+                physics_body
+            );
+        }
     }
 }
 
@@ -202,17 +216,20 @@ where
     if let Some(handle) = physics.body_handles.remove(&id) {
         // remove body if it still exists in the PhysicsWorld
         physics.bodies.remove(handle);
-        info!("Removed rigid body from world with id: {}", id);
+        trace!("Removed rigid body from world with id: {}", id);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        nalgebra::Isometry3,
-        nphysics::object::BodyStatus,
+        nalgebra::{Isometry3, Matrix3, Vector3},
+        nphysics::{algebra::Force3, object::BodyStatus},
+        parameters::Gravity,
+        physics_dispatcher,
         systems::SyncBodiesToPhysicsSystem,
         Physics,
+        PhysicsBody,
         PhysicsBodyBuilder,
         SimplePosition,
     };
@@ -246,4 +263,76 @@ mod tests {
         assert_eq!(physics.body_handles.len(), 1);
         assert_eq!(physics.bodies.iter().count(), 2);
     }
+
+    #[test]
+    fn teleport_rigid_body() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with the PhysicsBody component and execute the dispatcher
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        // teleport the Entity by editing its Position
+        world
+            .write_storage::<SimplePosition<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .0 = Isometry3::translation(5.0, 0.0, 0.0);
+        dispatcher.dispatch(&world);
+
+        // the RigidBody followed the manual edit
+        let physics = world.read_resource::<Physics<f32>>();
+        let handle = physics.body_handles.get(&entity.id()).unwrap();
+        let rigid_body = physics.bodies.rigid_body(*handle).unwrap();
+        assert_eq!(rigid_body.position().translation.vector.x, 5.0);
+    }
+
+    #[test]
+    fn lock_axes() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity that may only move horizontally and never rotate
+        *world.write_resource::<Gravity<f32>>() = Gravity(Vector3::new(0.0, -9.81, 0.0));
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .angular_inertia(Matrix3::identity())
+                    .lock_rotations(true)
+                    .translations_kinematic(Vector3::new(false, true, false))
+                    .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+
+        // apply a torque and a force along all axes
+        world
+            .write_storage::<PhysicsBody<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .apply_external_force(&Force3::new(
+                Vector3::new(10.0, 10.0, 0.0),
+                Vector3::new(10.0, 10.0, 10.0),
+            ));
+        for _ in 0..10 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the Entity neither fell, nor rotated, but moved along the x axis
+        let positions = world.read_storage::<SimplePosition<f32>>();
+        let isometry = positions.get(entity).unwrap().0;
+        assert!(isometry.translation.vector.x > 0.0);
+        assert_eq!(isometry.translation.vector.y, 0.0);
+        assert_eq!(isometry.rotation, Isometry3::<f32>::identity().rotation);
+    }
 }
@@ -4,20 +4,27 @@ use specs::{
     storage::ComponentEvent,
     world::Index,
     Join,
+    Read,
     ReadStorage,
     ReaderId,
     System,
     SystemData,
     World,
+    Write,
     WriteExpect,
     WriteStorage,
 };
 
 use crate::{
     bodies::Position,
-    colliders::PhysicsCollider,
-    nalgebra::RealField,
-    nphysics::object::{BodyPartHandle, ColliderDesc},
+    colliders::{PhysicsCollider, ShapeCache},
+    nalgebra::{Isometry3, RealField},
+    ncollide::pipeline::GeometricQueryType,
+    nphysics::{
+        material::BasicMaterial,
+        object::{BodyPartHandle, ColliderDesc, DefaultBodyHandle},
+    },
+    parameters::PhysicsLogVerbosity,
     Physics,
     PhysicsParent,
 };
@@ -42,12 +49,22 @@ where
     type SystemData = (
         ReadStorage<'s, P>,
         ReadStorage<'s, PhysicsParent>,
+        Option<Read<'s, PhysicsLogVerbosity>>,
         WriteExpect<'s, Physics<N>>,
+        Write<'s, ShapeCache<N>>,
         WriteStorage<'s, PhysicsCollider<N>>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (positions, parent_entities, mut physics, mut physics_colliders) = data;
+        let (
+            positions,
+            parent_entities,
+            verbosity,
+            mut physics,
+            mut shape_cache,
+            mut physics_colliders,
+        ) = data;
+        let verbosity = verbosity.map_or(PhysicsLogVerbosity::default(), |verbosity| *verbosity);
 
         // collect all ComponentEvents for the Position storage
         let (inserted_positions, ..) =
@@ -59,7 +76,7 @@ where
                 &physics_colliders,
                 self.physics_colliders_reader_id.as_mut().unwrap(),
             );
-        
+
         // handle removed events
         for id in &removed_physics_colliders {
             remove_collider::<N, P>(id, &mut physics);
@@ -86,14 +103,24 @@ where
                     parent_entity,
                     &position,
                     &mut physics,
+                    &mut shape_cache,
                     physics_collider.get_mut_unchecked(),
+                    verbosity,
                 );
             }
 
             // handle modified events
             if modified_physics_colliders.contains(id) {
                 debug!("Modified PhysicsCollider with id: {}", id);
-                update_collider::<N, P>(id, &mut physics, physics_collider.get_unchecked());
+                update_collider::<N, P>(
+                    id,
+                    parent_entity,
+                    &position,
+                    &mut physics,
+                    &mut shape_cache,
+                    physics_collider.get_mut_unchecked(),
+                    verbosity,
+                );
             }
         }
 
@@ -102,6 +129,9 @@ where
             .channel()
             .read(self.physics_colliders_reader_id.as_mut().unwrap());
         for _ in event_iter {}
+
+        // drop the cached meshes of removed or reshaped PhysicsColliders
+        shape_cache.evict_unused();
     }
 
     fn setup(&mut self, res: &mut World) {
@@ -142,7 +172,9 @@ fn add_collider<N, P>(
     parent_entity: Option<&PhysicsParent>,
     position: &P,
     physics: &mut Physics<N>,
+    shape_cache: &mut ShapeCache<N>,
     physics_collider: &mut PhysicsCollider<N>,
+    verbosity: PhysicsLogVerbosity,
 ) where
     N: RealField,
     P: Position<N>,
@@ -153,6 +185,48 @@ fn add_collider<N, P>(
         physics.colliders.remove(handle);
     }
 
+    let (parent_part_handle, translation) =
+        parent_and_position(id, parent_entity, position, physics, physics_collider);
+
+    // create the actual Collider in the nphysics World and fetch its handle
+    let handle = physics.colliders.insert(
+        ColliderDesc::new(physics_collider.shape_handle(shape_cache))
+            .position(translation)
+            .density(physics_collider.density)
+            .material(physics_collider.material.clone())
+            .margin(physics_collider.margin)
+            .collision_groups(physics_collider.collision_groups)
+            .linear_prediction(physics_collider.linear_prediction)
+            .angular_prediction(physics_collider.angular_prediction)
+            .sensor(physics_collider.sensor)
+            .user_data(id)
+            .build(parent_part_handle),
+    );
+
+    physics_collider.handle = Some(handle);
+    physics.collider_handles.insert(id, handle);
+
+    if verbosity == PhysicsLogVerbosity::Verbose {
+        trace!(
+            "Inserted collider to world with values: {:?}",
+            physics_collider
+        );
+    }
+}
+
+/// Returns the `BodyPartHandle` of the body the `Collider` of the given
+/// `PhysicsCollider` is attached to and its position relative to that body.
+fn parent_and_position<N, P>(
+    id: Index,
+    parent_entity: Option<&PhysicsParent>,
+    position: &P,
+    physics: &Physics<N>,
+    physics_collider: &PhysicsCollider<N>,
+) -> (BodyPartHandle<DefaultBodyHandle>, Isometry3<N>)
+where
+    N: RealField,
+    P: Position<N>,
+{
     // attempt to find an existing RigidBody for this Index; if one exists we'll
     // fetch its BodyPartHandle and use it as the Colliders parent in the
     // nphysics World
@@ -185,7 +259,9 @@ fn add_collider<N, P>(
 
     // translation based on parent handle; if we did not have a valid parent and
     // ended up defaulting to BodyPartHandle::ground(), we'll need to take the
-    // Position into consideration
+    // Position into consideration; otherwise the offset is relative to the parent
+    // body part and nphysics composes it with the full parent isometry, including
+    // its rotation
     let translation = if parent_part_handle == ground_handle {
         // let scale = 1.0; may be added later
         let iso = &mut position.isometry().clone();
@@ -197,46 +273,88 @@ fn add_collider<N, P>(
         physics_collider.offset_from_parent
     };
 
-    // create the actual Collider in the nphysics World and fetch its handle
-    let handle = physics.colliders.insert(
-        ColliderDesc::new(physics_collider.shape_handle())
-            .position(translation)
-            .density(physics_collider.density)
-            .material(physics_collider.material.clone())
-            .margin(physics_collider.margin)
-            .collision_groups(physics_collider.collision_groups)
-            .linear_prediction(physics_collider.linear_prediction)
-            .angular_prediction(physics_collider.angular_prediction)
-            .sensor(physics_collider.sensor)
-            .user_data(id)
-            .build(parent_part_handle),
-    );
-
-    physics_collider.handle = Some(handle);
-    physics.collider_handles.insert(id, handle);
-
-    info!(
-        "Inserted collider to world with values: {:?}",
-        physics_collider
-    );
+    (parent_part_handle, translation)
 }
 
-fn update_collider<N, P>(id: Index, physics: &mut Physics<N>, physics_collider: &PhysicsCollider<N>)
-where
+fn update_collider<N, P>(
+    id: Index,
+    parent_entity: Option<&PhysicsParent>,
+    position: &P,
+    physics: &mut Physics<N>,
+    shape_cache: &mut ShapeCache<N>,
+    physics_collider: &mut PhysicsCollider<N>,
+    verbosity: PhysicsLogVerbosity,
+) where
     N: RealField,
     P: Position<N>,
 {
     debug!("Modified PhysicsCollider with id: {}", id);
-    let collider_handle = physics_collider.handle.unwrap();
-    let collider = physics.colliders.get_mut(collider_handle).unwrap();
+    // the collider may have been removed implicitly together with its parent body
+    let handle = match physics.collider_handles.get(&id) {
+        Some(handle) if physics.colliders.get(*handle).is_some() => *handle,
+        _ => {
+            warn!("Collider with id {} no longer exists in the world", id);
+            physics.collider_handles.remove(&id);
+            return;
+        }
+    };
 
-    // update collision groups
-    collider.set_collision_groups(physics_collider.collision_groups);
+    // nphysics does not support changing the shape, density, margin or position
+    // of an existing Collider relative to its body, so it is rebuilt if any of
+    // these changed; this drops the ongoing contacts of the Collider. Materials
+    // can only be replaced in place if both are BasicMaterials
+    let (_, translation) =
+        parent_and_position(id, parent_entity, position, physics, physics_collider);
+    let collider = physics.colliders.get_mut(handle).unwrap();
+    let rebuild = physics_collider.shape_changed()
+        || collider.density() != physics_collider.density
+        || collider.margin() != physics_collider.margin
+        || *collider.position_wrt_body() != translation
+        || match (
+            collider.material_mut().downcast_mut::<BasicMaterial<N>>(),
+            physics_collider.material.downcast_ref::<BasicMaterial<N>>(),
+        ) {
+            (Some(current), Some(material)) => {
+                *current = *material;
+                false
+            }
+            _ => true,
+        };
 
-    info!(
-        "Updated collider in world with values: {:?}",
-        physics_collider
-    );
+    if rebuild {
+        physics.collider_handles.remove(&id);
+        physics.colliders.remove(handle);
+        add_collider::<N, P>(
+            id,
+            parent_entity,
+            position,
+            physics,
+            shape_cache,
+            physics_collider,
+            PhysicsLogVerbosity::Quiet,
+        );
+    } else {
+        // all other values are applied to the existing Collider, keeping its
+        // contacts
+        let query_type = if physics_collider.sensor {
+            GeometricQueryType::Proximity(physics_collider.linear_prediction)
+        } else {
+            GeometricQueryType::Contacts(
+                physics_collider.margin + physics_collider.linear_prediction,
+                physics_collider.angular_prediction,
+            )
+        };
+        collider.set_collision_groups(physics_collider.collision_groups);
+        collider.set_query_type(query_type);
+        collider.set_user_data(Some(Box::new(id)));
+    }
+
+    if verbosity == PhysicsLogVerbosity::Verbose {
+        trace!(
+            "Updated collider in world with values: {:?}",
+            physics_collider
+        );
+    }
 }
 
 fn remove_collider<N, P>(id: Index, physics: &mut Physics<N>)
@@ -254,7 +372,7 @@ where
             physics.colliders.remove(handle);
         }
 
-        info!("Removed collider from world with id: {}", id);
+        trace!("Removed collider from world with id: {}", id);
     }
 }
 
@@ -263,11 +381,22 @@ mod tests {
     use specs::prelude::*;
 
     use crate::{
-        colliders::Shape,
-        nalgebra::Isometry3,
+        colliders::{IntoMesh, MeshData, MeshHandle, PhysicsCollider, Shape, ShapeCache},
+        nalgebra::{Isometry3, Point3, Vector3},
+        ncollide::{
+            pipeline::CollisionGroups,
+            shape::{self, Ball},
+        },
+        nphysics::{
+            material::{BasicMaterial, MaterialHandle},
+            object::BodyStatus,
+        },
+        physics_dispatcher,
         systems::SyncCollidersToPhysicsSystem,
         Physics,
+        PhysicsBodyBuilder,
         PhysicsColliderBuilder,
+        PhysicsParent,
         SimplePosition,
     };
 
@@ -299,4 +428,305 @@ mod tests {
         assert_eq!(physics.collider_handles.len(), 1);
         assert_eq!(physics.colliders.iter().count(), 1);
     }
+
+    #[test]
+    fn remove_collider() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with a PhysicsBody and PhysicsCollider as well as a child
+        // Entity whose PhysicsCollider is attached to the parents PhysicsBody
+        let parent = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+            .build();
+        let child = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+            .with(PhysicsParent { entity: parent })
+            .build();
+        dispatcher.dispatch(&world);
+
+        {
+            let physics = world.read_resource::<Physics<f32>>();
+            assert_eq!(physics.collider_handles.len(), 2);
+            assert_eq!(physics.colliders.iter().count(), 2);
+        }
+
+        // delete both Entities in the same frame and execute the dispatcher
+        world.delete_entities(&[parent, child]).unwrap();
+        world.maintain();
+        dispatcher.dispatch(&world);
+
+        // neither the body nor any of the colliders remain
+        let physics = world.read_resource::<Physics<f32>>();
+        assert!(physics.body_handles.is_empty());
+        assert!(physics.collider_handles.is_empty());
+        assert_eq!(physics.bodies.iter().count(), 1);
+        assert_eq!(physics.colliders.iter().count(), 0);
+    }
+
+    #[test]
+    fn cache_shape_handle() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with the PhysicsCollider component and execute the
+        // dispatcher; the ShapeHandle is cached on insertion
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+        assert!(world
+            .read_storage::<PhysicsCollider<f32>>()
+            .get(entity)
+            .unwrap()
+            .shape_handle
+            .is_some());
+
+        // modifying the Shape of the PhysicsCollider invalidates the cache
+        world
+            .write_storage::<PhysicsCollider<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .set_shape(Shape::Ball { radius: 2.0 });
+        dispatcher.dispatch(&world);
+
+        // and the Collider is rebuilt from the modified Shape
+        let physics_colliders = world.read_storage::<PhysicsCollider<f32>>();
+        let shape_handle = physics_colliders
+            .get(entity)
+            .unwrap()
+            .shape_handle
+            .clone()
+            .unwrap();
+        assert_eq!(shape_handle.as_shape::<Ball<f32>>().unwrap().radius(), 2.0);
+    }
+
+    #[derive(Clone)]
+    struct Quad;
+
+    impl IntoMesh for Quad {
+        type N = f32;
+
+        fn points(&self) -> MeshData<f32> {
+            let points = vec![
+                Point3::new(-1.0, 0.0, -1.0),
+                Point3::new(1.0, 0.0, -1.0),
+                Point3::new(1.0, 0.0, 1.0),
+                Point3::new(-1.0, 0.0, 1.0),
+            ];
+            let indices = vec![Point3::new(0, 1, 2), Point3::new(0, 2, 3)];
+
+            (points, indices, None)
+        }
+    }
+
+    #[test]
+    fn share_shape_handle() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create two Entities with separately built PhysicsColliders of the same
+        // MeshHandle and execute the dispatcher
+        let mesh_handle = MeshHandle::new(Quad);
+        let mut create_quad = || {
+            world
+                .create_entity()
+                .with(SimplePosition::<f32>(Isometry3::identity()))
+                .with(
+                    PhysicsColliderBuilder::<f32>::from(Shape::TriMesh {
+                        handle: mesh_handle.clone(),
+                    })
+                    .build(),
+                )
+                .build()
+        };
+        let quad1 = create_quad();
+        let quad2 = create_quad();
+        dispatcher.dispatch(&world);
+
+        // the TriMesh was only built once and both Colliders share its ShapeHandle
+        assert_eq!(world.read_resource::<ShapeCache<f32>>().len(), 1);
+        {
+            let physics = world.read_resource::<Physics<f32>>();
+            let shape = |entity: Entity| {
+                let handle = physics.collider_handles.get(&entity.id()).unwrap();
+                physics
+                    .colliders
+                    .get(*handle)
+                    .unwrap()
+                    .shape_handle()
+                    .as_ref() as *const dyn shape::Shape<f32> as *const ()
+            };
+            assert_eq!(shape(quad1), shape(quad2));
+        }
+
+        // a separately created MeshHandle is built again, even if it generates the
+        // same MeshData
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::TriMesh {
+                    handle: MeshHandle::new(Quad),
+                })
+                .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+        assert_eq!(world.read_resource::<ShapeCache<f32>>().len(), 2);
+    }
+
+    #[test]
+    fn evict_unused_meshes() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with a TriMesh PhysicsCollider whose MeshHandle is only
+        // referenced by its Shape and execute the dispatcher
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::TriMesh {
+                    handle: MeshHandle::new(Quad),
+                })
+                .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+        assert_eq!(world.read_resource::<ShapeCache<f32>>().len(), 1);
+
+        // replacing the Shape evicts the mesh from the ShapeCache
+        world
+            .write_storage::<PhysicsCollider<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .set_shape(Shape::Ball { radius: 1.0 });
+        dispatcher.dispatch(&world);
+        assert!(world.read_resource::<ShapeCache<f32>>().is_empty());
+    }
+
+    #[test]
+    fn offset_from_rotated_parent() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create a PhysicsBody rotated by 90° around the z axis and a child
+        // Entity whose PhysicsCollider is offset along the x axis of its parent
+        let parent = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::new(
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::z() * std::f32::consts::FRAC_PI_2,
+            )))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Static).build())
+            .build();
+        let child = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 })
+                    .offset_from_parent(Isometry3::translation(1.0, 0.0, 0.0))
+                    .build(),
+            )
+            .with(PhysicsParent { entity: parent })
+            .build();
+        dispatcher.dispatch(&world);
+
+        // the offset is rotated along with the parent and points along the y axis
+        let physics = world.read_resource::<Physics<f32>>();
+        let handle = physics.collider_handles.get(&child.id()).unwrap();
+        let translation = physics
+            .colliders
+            .get(*handle)
+            .unwrap()
+            .position()
+            .translation
+            .vector;
+        assert!((translation - Vector3::new(1.0, 1.0, 0.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn update_collider_in_place() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with the PhysicsCollider component and execute the
+        // dispatcher
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+        let handle = *world
+            .read_resource::<Physics<f32>>()
+            .collider_handles
+            .get(&entity.id())
+            .unwrap();
+
+        // change the collision groups, material and sensor flag of the
+        // PhysicsCollider
+        {
+            let mut physics_colliders = world.write_storage::<PhysicsCollider<f32>>();
+            let physics_collider = physics_colliders.get_mut(entity).unwrap();
+            physics_collider.collision_groups = CollisionGroups::new().with_membership(&[3]);
+            physics_collider.material = MaterialHandle::new(BasicMaterial::new(1.0, 0.0));
+            physics_collider.sensor = true;
+        }
+        dispatcher.dispatch(&world);
+
+        // the existing Collider was updated instead of being rebuilt
+        let physics = world.read_resource::<Physics<f32>>();
+        assert_eq!(physics.collider_handles.get(&entity.id()), Some(&handle));
+        let collider = physics.colliders.get(handle).unwrap();
+        assert!(collider.collision_groups().is_member_of(3));
+        assert!(!collider.collision_groups().is_member_of(0));
+        assert!(collider.is_sensor());
+    }
+
+    #[test]
+    fn rebuild_modified_collider() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with the PhysicsCollider component and execute the
+        // dispatcher
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        // change the margin and material of the PhysicsCollider
+        {
+            let mut physics_colliders = world.write_storage::<PhysicsCollider<f32>>();
+            let physics_collider = physics_colliders.get_mut(entity).unwrap();
+            physics_collider.margin = 0.5;
+            physics_collider.material = MaterialHandle::new(BasicMaterial::new(1.0, 0.0));
+        }
+        dispatcher.dispatch(&world);
+
+        // the Collider in the physics world reflects the new values
+        let physics = world.read_resource::<Physics<f32>>();
+        assert_eq!(physics.collider_handles.len(), 1);
+        assert_eq!(physics.colliders.iter().count(), 1);
+        let handle = physics.collider_handles.get(&entity.id()).unwrap();
+        assert_eq!(physics.colliders.get(*handle).unwrap().margin(), 0.5);
+    }
 }
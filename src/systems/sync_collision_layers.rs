@@ -0,0 +1,64 @@
+use std::marker::PhantomData;
+
+use specs::{Join, Read, ReadStorage, System, SystemData, World, WriteStorage};
+
+use crate::{
+    colliders::PhysicsCollider,
+    layers::{same_collision_groups, CollisionMatrix, Layer},
+    nalgebra::RealField,
+};
+
+/// The `SyncCollisionLayersSystem` compiles the `CollisionGroups` of
+/// `PhysicsCollider`s with a `Layer` `Component` from the `CollisionMatrix`.
+/// `PhysicsCollider`s are only modified if their `CollisionGroups` actually
+/// changed, as modifying them rebuilds their `Collider`.
+pub struct SyncCollisionLayersSystem<N> {
+    n_marker: PhantomData<N>,
+}
+
+impl<'s, N: RealField> System<'s> for SyncCollisionLayersSystem<N> {
+    type SystemData = (
+        Read<'s, CollisionMatrix>,
+        ReadStorage<'s, Layer>,
+        WriteStorage<'s, PhysicsCollider<N>>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (collision_matrix, layers, mut physics_colliders) = data;
+
+        for (layer, mut physics_collider) in (&layers, &mut physics_colliders.restrict_mut()).join()
+        {
+            let collision_groups = match collision_matrix.collision_groups(layer.0) {
+                Some(collision_groups) => collision_groups,
+                None => {
+                    warn!("Undeclared collision layer: {}", layer.0);
+                    continue;
+                }
+            };
+
+            if !same_collision_groups(
+                &physics_collider.get_unchecked().collision_groups,
+                &collision_groups,
+            ) {
+                // mutable access flags the PhysicsCollider as modified
+                physics_collider.get_mut_unchecked().collision_groups = collision_groups;
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("SyncCollisionLayersSystem.setup");
+        Self::SystemData::setup(res);
+    }
+}
+
+impl<N> Default for SyncCollisionLayersSystem<N>
+where
+    N: RealField,
+{
+    fn default() -> Self {
+        Self {
+            n_marker: PhantomData,
+        }
+    }
+}
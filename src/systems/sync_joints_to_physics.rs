@@ -0,0 +1,680 @@
+use std::marker::PhantomData;
+
+use specs::{
+    storage::ComponentEvent,
+    world::Index,
+    Entities,
+    Entity,
+    Join,
+    Read,
+    ReaderId,
+    System,
+    SystemData,
+    World,
+    WriteExpect,
+    WriteStorage,
+};
+
+use crate::{
+    joints::{
+        BallMarker,
+        FixedMarker,
+        JointLimits,
+        JointType,
+        PhysicsJoint,
+        PrismaticMarker,
+        RevoluteMarker,
+    },
+    nalgebra::{self as na, Point3, RealField, Unit, Vector3},
+    nphysics::{
+        algebra::{Force3, ForceType},
+        joint::{BallConstraint, FixedConstraint, PrismaticConstraint, RevoluteConstraint},
+        object::{Body, BodyPartHandle, BodyStatus, DefaultBodyHandle, RigidBody},
+    },
+    parameters::{is_stepping, PhysicsLogVerbosity, PhysicsPaused, StepOnce},
+    Physics,
+};
+
+use super::iterate_component_events;
+
+/// The `SyncJointsToPhysicsSystem` handles the synchronisation of
+/// `PhysicsJoint` `Component`s into the physics `World`. It also keeps the
+/// joint marker `Component`s in line with the `JointType` of each
+/// `PhysicsJoint`. `JointMotor`s are not driven while the simulation is
+/// paused.
+pub struct SyncJointsToPhysicsSystem<N> {
+    physics_joints_reader_id: Option<ReaderId<ComponentEvent>>,
+
+    n_marker: PhantomData<N>,
+}
+
+impl<'s, N: RealField> System<'s> for SyncJointsToPhysicsSystem<N> {
+    type SystemData = (
+        Entities<'s>,
+        Option<Read<'s, PhysicsLogVerbosity>>,
+        Option<Read<'s, PhysicsPaused>>,
+        Option<Read<'s, StepOnce>>,
+        WriteExpect<'s, Physics<N>>,
+        WriteStorage<'s, PhysicsJoint<N>>,
+        JointMarkerStorages<'s>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, verbosity, paused, step_once, mut physics, mut physics_joints, mut markers) =
+            data;
+        let verbosity = verbosity.map_or(PhysicsLogVerbosity::default(), |verbosity| *verbosity);
+
+        // collect all ComponentEvents for the PhysicsJoint storage
+        let (inserted_physics_joints, modified_physics_joints, removed_physics_joints) =
+            iterate_component_events(
+                &physics_joints,
+                self.physics_joints_reader_id.as_mut().unwrap(),
+            );
+
+        // handle removed events
+        for id in &removed_physics_joints {
+            remove_joint::<N>(id, &mut physics);
+            markers.clear(entities.entity(id));
+        }
+
+        // iterate over PhysicsJoint components with an id/Index that exists in either
+        // the inserted or modified BitSet; modified joints are simply rebuilt as
+        // nphysics joint constraints can't be reconfigured after their creation
+        for (entity, mut physics_joint, id) in (
+            &entities,
+            &mut physics_joints.restrict_mut(),
+            &inserted_physics_joints | &modified_physics_joints,
+        )
+            .join()
+        {
+            if inserted_physics_joints.contains(id) {
+                debug!("Inserted PhysicsJoint with id: {}", id);
+            } else {
+                debug!("Modified PhysicsJoint with id: {}", id);
+                remove_joint::<N>(id, &mut physics);
+            }
+
+            let physics_joint = physics_joint.get_mut_unchecked();
+            add_joint::<N>(id, &mut physics, physics_joint, verbosity);
+            markers.set(entity, &physics_joint.joint_type);
+        }
+
+        // drive all joints with an enabled JointMotor, unless the simulation is
+        // paused
+        if is_stepping(paused.as_deref(), step_once.as_deref()) {
+            for (entity, physics_joint) in (&entities, &physics_joints).join() {
+                apply_joint_motor::<N>(entity.id(), &mut physics, physics_joint);
+            }
+        }
+
+        // Drain update triggers caused by inserts
+        let event_iter = physics_joints
+            .channel()
+            .read(self.physics_joints_reader_id.as_mut().unwrap());
+        for _ in event_iter {}
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("SyncJointsToPhysicsSystem.setup");
+        Self::SystemData::setup(res);
+
+        // initialise required resources
+        res.entry::<Physics<N>>().or_insert_with(Physics::default);
+
+        // register reader id for the PhysicsJoint storage
+        let mut physics_joint_storage: WriteStorage<PhysicsJoint<N>> = SystemData::fetch(&res);
+        self.physics_joints_reader_id = Some(physics_joint_storage.register_reader());
+    }
+}
+
+impl<N> Default for SyncJointsToPhysicsSystem<N>
+where
+    N: RealField,
+{
+    fn default() -> Self {
+        Self {
+            physics_joints_reader_id: None,
+            n_marker: PhantomData,
+        }
+    }
+}
+
+type JointMarkerStorages<'s> = (
+    WriteStorage<'s, RevoluteMarker>,
+    WriteStorage<'s, PrismaticMarker>,
+    WriteStorage<'s, BallMarker>,
+    WriteStorage<'s, FixedMarker>,
+);
+
+trait JointMarkers {
+    fn clear(&mut self, entity: Entity);
+    fn set<N: RealField>(&mut self, entity: Entity, joint_type: &JointType<N>);
+}
+
+impl<'s> JointMarkers for JointMarkerStorages<'s> {
+    fn clear(&mut self, entity: Entity) {
+        self.0.remove(entity);
+        self.1.remove(entity);
+        self.2.remove(entity);
+        self.3.remove(entity);
+    }
+
+    fn set<N: RealField>(&mut self, entity: Entity, joint_type: &JointType<N>) {
+        self.clear(entity);
+
+        let result = match joint_type {
+            JointType::Revolute(_) => self.0.insert(entity, RevoluteMarker).map(|_| ()),
+            JointType::Prismatic(_) => self.1.insert(entity, PrismaticMarker).map(|_| ()),
+            JointType::Ball(_) => self.2.insert(entity, BallMarker).map(|_| ()),
+            JointType::Fixed(_) => self.3.insert(entity, FixedMarker).map(|_| ()),
+        };
+
+        if let Err(error) = result {
+            warn!("Failed to insert joint marker: {:?}", error);
+        }
+    }
+}
+
+// applies the break force and torque of a PhysicsJoint to the given joint
+// constraint; the constraint types share no common trait for this
+macro_rules! set_break_limits {
+    ($constraint:ident, $physics_joint:ident) => {
+        if let Some(break_force) = $physics_joint.break_force {
+            $constraint.set_break_force(break_force);
+        }
+        if let Some(break_torque) = $physics_joint.break_torque {
+            $constraint.set_break_torque(break_torque);
+        }
+    };
+}
+
+fn add_joint<N>(
+    id: Index,
+    physics: &mut Physics<N>,
+    physics_joint: &mut PhysicsJoint<N>,
+    verbosity: PhysicsLogVerbosity,
+) where
+    N: RealField,
+{
+    // remove already existing joints for this inserted event
+    if let Some(handle) = physics.joint_handles.remove(&id) {
+        warn!("Removing orphaned joint handle: {:?}", handle);
+        physics.joint_constraints.remove(handle);
+    }
+
+    // a joint requires a RigidBody on its own Entity; the connected Entity is
+    // optional and defaults to the ground
+    let body_part_1 = match physics.body_handles.get(&id) {
+        Some(handle) => BodyPartHandle(*handle, 0),
+        None => {
+            warn!(
+                "Unable to find a PhysicsBody for PhysicsJoint with id: {}",
+                id
+            );
+            return;
+        }
+    };
+    let body_part_2 = match physics_joint.connected_entity {
+        Some(connected_entity) => match physics.body_handles.get(&connected_entity.id()) {
+            Some(handle) => BodyPartHandle(*handle, 0),
+            None => {
+                warn!(
+                    "Unable to find a PhysicsBody for the connected Entity of PhysicsJoint with \
+                     id: {}",
+                    id
+                );
+                return;
+            }
+        },
+        None => BodyPartHandle(physics.ground, 0),
+    };
+
+    // create the actual joint constraint in the nphysics World and fetch its
+    // handle; limits are only supported by revolute and prismatic constraints
+    let limits = physics_joint.limits.unwrap_or(JointLimits {
+        min: None,
+        max: None,
+    });
+    let handle = match physics_joint.joint_type {
+        JointType::Revolute(joint) => {
+            let mut constraint = RevoluteConstraint::new(
+                body_part_1,
+                body_part_2,
+                joint.local_anchor_1,
+                joint.local_axis_1,
+                joint.local_anchor_2,
+                joint.local_axis_2,
+            );
+            if let Some(min) = limits.min {
+                constraint.enable_min_angle(min);
+            }
+            if let Some(max) = limits.max {
+                constraint.enable_max_angle(max);
+            }
+            set_break_limits!(constraint, physics_joint);
+            physics.joint_constraints.insert(constraint)
+        }
+        JointType::Prismatic(joint) => {
+            let mut constraint = PrismaticConstraint::new(
+                body_part_1,
+                body_part_2,
+                joint.local_anchor_1,
+                joint.local_axis_1,
+                joint.local_anchor_2,
+            );
+            if let Some(min) = limits.min {
+                constraint.enable_min_offset(min);
+            }
+            if let Some(max) = limits.max {
+                constraint.enable_max_offset(max);
+            }
+            set_break_limits!(constraint, physics_joint);
+            physics.joint_constraints.insert(constraint)
+        }
+        JointType::Ball(joint) => {
+            let mut constraint = BallConstraint::new(
+                body_part_1,
+                body_part_2,
+                joint.local_anchor_1,
+                joint.local_anchor_2,
+            );
+            set_break_limits!(constraint, physics_joint);
+            physics.joint_constraints.insert(constraint)
+        }
+        JointType::Fixed(joint) => {
+            let mut constraint = FixedConstraint::new(
+                body_part_1,
+                body_part_2,
+                Point3::from(joint.local_anchor_1.translation.vector),
+                joint.local_anchor_1.rotation,
+                Point3::from(joint.local_anchor_2.translation.vector),
+                joint.local_anchor_2.rotation,
+            );
+            set_break_limits!(constraint, physics_joint);
+            physics.joint_constraints.insert(constraint)
+        }
+    };
+
+    physics_joint.handle = Some(handle);
+    physics.joint_handles.insert(id, handle);
+
+    if verbosity == PhysicsLogVerbosity::Verbose {
+        trace!("Inserted joint to world with values: {:?}", physics_joint);
+    }
+}
+
+fn apply_joint_motor<N>(id: Index, physics: &mut Physics<N>, physics_joint: &PhysicsJoint<N>)
+where
+    N: RealField,
+{
+    let motor = match physics_joint.motor {
+        Some(motor) if motor.enabled => motor,
+        _ => return,
+    };
+    let handle_1 = match physics.body_handles.get(&id) {
+        Some(handle) => *handle,
+        None => return,
+    };
+    let handle_2 = physics_joint
+        .connected_entity
+        .and_then(|entity| physics.body_handles.get(&entity.id()).cloned());
+
+    // the motor drives the relative velocity along the joint axis towards the
+    // desired velocity by applying an impulse to both bodies; the impulse is
+    // bounded by the maximum force the motor may apply over a single timestep
+    let max_impulse = motor.max_force * physics.timestep();
+    let bodies = &physics.bodies;
+    let impulse = match physics_joint.joint_type {
+        JointType::Revolute(joint) => {
+            let (axis, velocity_1, inverse_inertia_1) = match bodies.rigid_body(handle_1) {
+                Some(rigid_body) => (
+                    rigid_body.position().rotation * joint.local_axis_1,
+                    rigid_body.velocity().angular,
+                    inverse_angular_inertia(rigid_body, &joint.local_axis_1),
+                ),
+                None => return,
+            };
+            let (velocity_2, inverse_inertia_2) = handle_2
+                .and_then(|handle| bodies.rigid_body(handle))
+                .map_or((Vector3::zeros(), N::zero()), |rigid_body| {
+                    (
+                        rigid_body.velocity().angular,
+                        inverse_angular_inertia(rigid_body, &joint.local_axis_2),
+                    )
+                });
+
+            let inverse_inertia = inverse_inertia_1 + inverse_inertia_2;
+            if inverse_inertia <= N::zero() {
+                return;
+            }
+            let velocity = (velocity_1 - velocity_2).dot(&axis);
+            let impulse = na::clamp(
+                (motor.desired_velocity - velocity) / inverse_inertia,
+                -max_impulse,
+                max_impulse,
+            );
+            Force3::new(Vector3::zeros(), axis.into_inner() * impulse)
+        }
+        JointType::Prismatic(joint) => {
+            let (axis, velocity_1, inverse_mass_1) = match bodies.rigid_body(handle_1) {
+                Some(rigid_body) => (
+                    rigid_body.position().rotation * joint.local_axis_1,
+                    rigid_body.velocity().linear,
+                    inverse_mass(rigid_body),
+                ),
+                None => return,
+            };
+            let (velocity_2, inverse_mass_2) = handle_2
+                .and_then(|handle| bodies.rigid_body(handle))
+                .map_or((Vector3::zeros(), N::zero()), |rigid_body| {
+                    (rigid_body.velocity().linear, inverse_mass(rigid_body))
+                });
+
+            let inverse_mass = inverse_mass_1 + inverse_mass_2;
+            if inverse_mass <= N::zero() {
+                return;
+            }
+            let velocity = (velocity_1 - velocity_2).dot(&axis);
+            let impulse = na::clamp(
+                (motor.desired_velocity - velocity) / inverse_mass,
+                -max_impulse,
+                max_impulse,
+            );
+            Force3::new(axis.into_inner() * impulse, Vector3::zeros())
+        }
+        // motors are only supported by revolute and prismatic joints
+        _ => return,
+    };
+
+    apply_impulse(physics, handle_1, &impulse);
+    if let Some(handle_2) = handle_2 {
+        apply_impulse(
+            physics,
+            handle_2,
+            &Force3::new(-impulse.linear, -impulse.angular),
+        );
+    }
+}
+
+fn inverse_angular_inertia<N>(rigid_body: &RigidBody<N>, local_axis: &Unit<Vector3<N>>) -> N
+where
+    N: RealField,
+{
+    let inertia = local_axis.dot(&(rigid_body.local_inertia().angular * local_axis.into_inner()));
+    if rigid_body.status() == BodyStatus::Dynamic && inertia > N::zero() {
+        N::one() / inertia
+    } else {
+        N::zero()
+    }
+}
+
+fn inverse_mass<N>(rigid_body: &RigidBody<N>) -> N
+where
+    N: RealField,
+{
+    let mass = rigid_body.local_inertia().linear;
+    if rigid_body.status() == BodyStatus::Dynamic && mass > N::zero() {
+        N::one() / mass
+    } else {
+        N::zero()
+    }
+}
+
+fn apply_impulse<N>(physics: &mut Physics<N>, handle: DefaultBodyHandle, impulse: &Force3<N>)
+where
+    N: RealField,
+{
+    if let Some(rigid_body) = physics.bodies.rigid_body_mut(handle) {
+        if rigid_body.status() == BodyStatus::Dynamic {
+            rigid_body.apply_force(0, impulse, ForceType::Impulse, true);
+        }
+    }
+}
+
+fn remove_joint<N>(id: Index, physics: &mut Physics<N>)
+where
+    N: RealField,
+{
+    debug!("Removed PhysicsJoint with id: {}", id);
+    if let Some(handle) = physics.joint_handles.remove(&id) {
+        // joints are implicitly removed alongside their bodies, so we have to make
+        // sure the joint still exists before attempting to remove it
+        if physics.joint_constraints.get(handle).is_some() {
+            physics.joint_constraints.remove(handle);
+        }
+
+        trace!("Removed joint from world with id: {}", id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        joints::{BallJoint, BallMarker, JointMotor, JointType, RevoluteJoint, RevoluteMarker},
+        nalgebra::{Isometry3, Matrix3, Point3, Vector3},
+        nphysics::object::BodyStatus,
+        parameters::PhysicsPaused,
+        physics_dispatcher,
+        systems::{SyncBodiesToPhysicsSystem, SyncJointsToPhysicsSystem},
+        Physics,
+        PhysicsBodyBuilder,
+        PhysicsJoint,
+        PhysicsJointBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn add_joint() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncBodiesToPhysicsSystem::<f32, SimplePosition<f32>>::default(),
+                "sync_bodies_to_physics_system",
+                &[],
+            )
+            .with(
+                SyncJointsToPhysicsSystem::<f32>::default(),
+                "sync_joints_to_physics_system",
+                &["sync_bodies_to_physics_system"],
+            )
+            .build();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with a PhysicsBody and a PhysicsJoint anchoring it to the
+        // ground and execute the dispatcher
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::<f32>::translation(
+                0.0, 1.0, 0.0,
+            )))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(
+                PhysicsJointBuilder::<f32>::from(JointType::Ball(BallJoint {
+                    local_anchor_1: Point3::new(0.0, 1.0, 0.0),
+                    local_anchor_2: Point3::new(0.0, 2.0, 0.0),
+                }))
+                .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+
+        {
+            // fetch the Physics instance and check for new joints
+            let physics = world.read_resource::<Physics<f32>>();
+            assert_eq!(physics.joint_handles.len(), 1);
+            assert_eq!(physics.joint_constraints.iter().count(), 1);
+        }
+
+        // a BallJoint must not be marked as RevoluteJoint
+        assert!(world.read_storage::<RevoluteMarker>().get(entity).is_none());
+    }
+
+    #[test]
+    fn drive_joint_motor() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity hinged to the ground and driven by a JointMotor
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(false)
+                    .mass(1.0)
+                    .angular_inertia(Matrix3::identity())
+                    .build(),
+            )
+            .with(
+                PhysicsJointBuilder::<f32>::from(JointType::Revolute(RevoluteJoint {
+                    local_anchor_1: Point3::origin(),
+                    local_axis_1: Vector3::z_axis(),
+                    local_anchor_2: Point3::origin(),
+                    local_axis_2: Vector3::z_axis(),
+                }))
+                .motor(JointMotor {
+                    desired_velocity: 2.0,
+                    max_force: 1000.0,
+                    enabled: true,
+                })
+                .build(),
+            )
+            .build();
+
+        for _ in 0..60 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the motor has spun the body up to the desired angular velocity
+        let physics = world.read_resource::<Physics<f32>>();
+        let handle = physics.body_handles.get(&entity.id()).unwrap();
+        let angular = physics
+            .bodies
+            .rigid_body(*handle)
+            .unwrap()
+            .velocity()
+            .angular;
+        assert!((angular.z - 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn pause_joint_motor() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity hinged to the ground and driven by a JointMotor while
+        // the simulation is paused
+        world.insert(PhysicsPaused(true));
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(false)
+                    .mass(1.0)
+                    .angular_inertia(Matrix3::identity())
+                    .build(),
+            )
+            .with(
+                PhysicsJointBuilder::<f32>::from(JointType::Revolute(RevoluteJoint {
+                    local_anchor_1: Point3::origin(),
+                    local_axis_1: Vector3::z_axis(),
+                    local_anchor_2: Point3::origin(),
+                    local_axis_2: Vector3::z_axis(),
+                }))
+                .motor(JointMotor {
+                    desired_velocity: 2.0,
+                    max_force: 1000.0,
+                    enabled: true,
+                })
+                .build(),
+            )
+            .build();
+        for _ in 0..10 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the motor didn't apply any torque while paused
+        let angular = |world: &World| {
+            let physics = world.read_resource::<Physics<f32>>();
+            let handle = physics.body_handles.get(&entity.id()).unwrap();
+            physics
+                .bodies
+                .rigid_body(*handle)
+                .unwrap()
+                .velocity()
+                .angular
+        };
+        assert_eq!(angular(&world), Vector3::zeros());
+
+        // and drives the body again once resumed
+        world.insert(PhysicsPaused(false));
+        dispatcher.dispatch(&world);
+        assert!(angular(&world).z > 0.0);
+    }
+
+    #[test]
+    fn modify_and_remove_joint() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+
+        // create an Entity with a PhysicsBody and a RevoluteJoint
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .with(
+                PhysicsJointBuilder::<f32>::from(JointType::Revolute(RevoluteJoint {
+                    local_anchor_1: Point3::origin(),
+                    local_axis_1: Vector3::z_axis(),
+                    local_anchor_2: Point3::origin(),
+                    local_axis_2: Vector3::z_axis(),
+                }))
+                .build(),
+            )
+            .build();
+        dispatcher.dispatch(&world);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .joint_constraints
+                .iter()
+                .count(),
+            1
+        );
+
+        // turn the joint into a BallJoint; the constraint is replaced
+        world
+            .write_storage::<PhysicsJoint<f32>>()
+            .get_mut(entity)
+            .unwrap()
+            .joint_type = JointType::Ball(BallJoint {
+            local_anchor_1: Point3::origin(),
+            local_anchor_2: Point3::origin(),
+        });
+        dispatcher.dispatch(&world);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .joint_constraints
+                .iter()
+                .count(),
+            1
+        );
+        assert!(world.read_storage::<RevoluteMarker>().get(entity).is_none());
+        assert!(world.read_storage::<BallMarker>().get(entity).is_some());
+
+        // remove the PhysicsJoint again
+        world.write_storage::<PhysicsJoint<f32>>().remove(entity);
+        dispatcher.dispatch(&world);
+        let physics = world.read_resource::<Physics<f32>>();
+        assert!(physics.joint_handles.is_empty());
+        assert_eq!(physics.joint_constraints.iter().count(), 0);
+        assert!(world.read_storage::<BallMarker>().get(entity).is_none());
+    }
+}
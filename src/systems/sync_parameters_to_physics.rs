@@ -50,7 +50,10 @@ impl<'s, N: RealField> System<'s> for SyncParametersToPhysicsSystem<N> {
         }
 
         if let Some(params) = integration_params {
-            if *params != *physics.integration_parameters() {
+            // nphysics requires at least a single CCD substep
+            if params.max_ccd_substeps == 0 {
+                warn!("Integration parameters with 0 max_ccd_substeps are invalid, ignoring them.");
+            } else if *params != *physics.integration_parameters() {
                 params.apply(&mut physics.mechanical_world.integration_parameters);
                 info!("Integration parameters have been updated.");
             }
@@ -83,10 +86,15 @@ mod tests {
     use specs::prelude::*;
 
     use crate::{
-        nalgebra::Vector3,
-        parameters::Gravity,
+        nalgebra::{Isometry3, Vector3},
+        nphysics::object::BodyStatus,
+        parameters::{Gravity, PhysicsIntegrationParameters, PhysicsProfilingEnabled},
+        physics_dispatcher,
         systems::SyncParametersToPhysicsSystem,
         Physics,
+        PhysicsBody,
+        PhysicsBodyBuilder,
+        SimplePosition,
     };
 
     #[test]
@@ -109,4 +117,131 @@ mod tests {
         assert_ulps_eq!(physics.mechanical_world.gravity.y, 2.0);
         assert_ulps_eq!(physics.mechanical_world.gravity.z, 3.0);
     }
+
+    #[test]
+    fn flip_gravity() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        world.insert(Gravity(Vector3::<f32>::new(0.0, -9.81, 0.0)));
+
+        // create a falling Entity and step the simulation a few times
+        let entity = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .gravity_enabled(true)
+                    .build(),
+            )
+            .build();
+        for _ in 0..5 {
+            dispatcher.dispatch(&world);
+        }
+        let falling_velocity = world
+            .read_storage::<PhysicsBody<f32>>()
+            .get(entity)
+            .unwrap()
+            .velocity
+            .linear
+            .y;
+        assert!(falling_velocity < 0.0);
+
+        // flip the gravity mid-simulation; the Entity is slowed down and starts rising
+        world.write_resource::<Gravity<f32>>().y = 9.81;
+        for _ in 0..10 {
+            dispatcher.dispatch(&world);
+        }
+        let physics_bodies = world.read_storage::<PhysicsBody<f32>>();
+        assert!(physics_bodies.get(entity).unwrap().velocity.linear.y > 0.0);
+    }
+
+    #[test]
+    fn enable_profiling() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        world.insert(PhysicsProfilingEnabled(true));
+
+        // create an Entity and step the simulation
+        world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+            .build();
+        dispatcher.dispatch(&world);
+
+        // the performance counters measured the last step
+        let physics = world.read_resource::<Physics<f32>>();
+        assert!(physics.performance_counters().enabled());
+        assert!(physics.performance_counters().step_time() > 0.0);
+    }
+
+    #[test]
+    fn update_max_ccd_substeps() {
+        let mut world = World::new();
+        let mut dispatcher = DispatcherBuilder::new()
+            .with(
+                SyncParametersToPhysicsSystem::<f32>::default(),
+                "sync_parameters_to_physics_system",
+                &[],
+            )
+            .build();
+        dispatcher.setup(&mut world);
+
+        world.insert(PhysicsIntegrationParameters::<f32> {
+            max_ccd_substeps: 4,
+            ..PhysicsIntegrationParameters::default()
+        });
+        dispatcher.dispatch(&world);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .integration_parameters()
+                .max_ccd_substeps,
+            4
+        );
+
+        // 0 substeps are rejected and the previous parameters stay in place
+        world
+            .write_resource::<PhysicsIntegrationParameters<f32>>()
+            .max_ccd_substeps = 0;
+        dispatcher.dispatch(&world);
+        assert_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .integration_parameters()
+                .max_ccd_substeps,
+            4
+        );
+    }
+
+    #[test]
+    fn update_error_reduction_parameter() {
+        let mut world = World::new();
+        let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        world.insert(PhysicsIntegrationParameters::<f32>::default());
+        dispatcher.dispatch(&world);
+        assert_ulps_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .integration_parameters()
+                .erp,
+            0.2
+        );
+
+        // tune the parameters live; the change is applied on the next dispatch
+        world
+            .write_resource::<PhysicsIntegrationParameters<f32>>()
+            .error_reduction_parameter = 0.5;
+        dispatcher.dispatch(&world);
+        assert_ulps_eq!(
+            world
+                .read_resource::<Physics<f32>>()
+                .integration_parameters()
+                .erp,
+            0.5
+        );
+    }
 }
@@ -0,0 +1,159 @@
+use specs::{
+    storage::ComponentEvent,
+    Read,
+    ReadStorage,
+    ReaderId,
+    System,
+    SystemData,
+    World,
+    Write,
+    WriteStorage,
+};
+
+use crate::{
+    events::{ProximityEvent, ProximityEvents, Trigger, TriggerEvent, TriggerEvents, TriggerType},
+    ncollide::query::Proximity,
+};
+
+use super::iterate_component_events;
+
+/// The `TriggerEventsSystem` maps the `ProximityEvent`s emitted by the
+/// `PhysicsStepperSystem` to `TriggerEvent`s for every `Entity` with a
+/// `Trigger` `Component`. It has to run after the `PhysicsStepperSystem`.
+#[derive(Default)]
+pub struct TriggerEventsSystem {
+    proximity_events_reader_id: Option<ReaderId<ProximityEvent>>,
+    triggers_reader_id: Option<ReaderId<ComponentEvent>>,
+}
+
+impl<'s> System<'s> for TriggerEventsSystem {
+    type SystemData = (
+        Read<'s, ProximityEvents>,
+        ReadStorage<'s, Trigger>,
+        Write<'s, TriggerEvents>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (proximity_events, triggers, mut trigger_events) = data;
+
+        // collect the Triggers created since the last run; colliders overlapping them
+        // right away are only reported if the Trigger asks for it
+        let (inserted_triggers, ..) =
+            iterate_component_events(&triggers, self.triggers_reader_id.as_mut().unwrap());
+
+        for proximity_event in
+            proximity_events.read(self.proximity_events_reader_id.as_mut().unwrap())
+        {
+            let trigger_type = if proximity_event.new_status == Proximity::Intersecting {
+                TriggerType::Enter
+            } else if proximity_event.prev_status == Proximity::Intersecting {
+                TriggerType::Exit
+            } else {
+                continue;
+            };
+
+            // either or both colliders of the ProximityEvent can be a Trigger
+            let pairs = [
+                (proximity_event.collider1, proximity_event.collider2),
+                (proximity_event.collider2, proximity_event.collider1),
+            ];
+            for &(trigger_entity, entity) in pairs.iter() {
+                let trigger = match triggers.get(trigger_entity) {
+                    Some(trigger) => trigger,
+                    None => continue,
+                };
+
+                if trigger_type == TriggerType::Enter
+                    && !trigger.enter_on_spawn
+                    && inserted_triggers.contains(trigger_entity.id())
+                {
+                    continue;
+                }
+
+                debug!(
+                    "Entity {:?} triggered {:?} of Trigger {:?}",
+                    entity, trigger_type, trigger_entity
+                );
+                trigger_events.single_write(TriggerEvent {
+                    trigger: trigger_entity,
+                    entity,
+                    trigger_type,
+                });
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut World) {
+        info!("TriggerEventsSystem.setup");
+        Self::SystemData::setup(res);
+
+        // register reader ids for the ProximityEvents and the Trigger storage
+        self.proximity_events_reader_id =
+            Some(res.fetch_mut::<ProximityEvents>().register_reader());
+        let mut trigger_storage: WriteStorage<Trigger> = SystemData::fetch(&res);
+        self.triggers_reader_id = Some(trigger_storage.register_reader());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::prelude::*;
+
+    use crate::{
+        all_physics_dispatcher,
+        colliders::Shape,
+        events::{Trigger, TriggerEvents, TriggerType},
+        nalgebra::{Isometry3, Vector3},
+        nphysics::{algebra::Velocity3, object::BodyStatus},
+        PhysicsBodyBuilder,
+        PhysicsColliderBuilder,
+        SimplePosition,
+    };
+
+    #[test]
+    fn enter_and_exit_trigger() {
+        let mut world = World::new();
+        let mut dispatcher = all_physics_dispatcher::<f32, SimplePosition<f32>>();
+        dispatcher.setup(&mut world);
+        let mut reader_id = world.write_resource::<TriggerEvents>().register_reader();
+
+        // create a trigger zone and a ball passing through it
+        let trigger = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::identity()))
+            .with(
+                PhysicsColliderBuilder::<f32>::from(Shape::Cuboid {
+                    half_extents: Vector3::new(1.0, 1.0, 1.0),
+                })
+                .sensor(true)
+                .build(),
+            )
+            .with(Trigger::default())
+            .build();
+        let ball = world
+            .create_entity()
+            .with(SimplePosition::<f32>(Isometry3::translation(
+                -3.0, 0.0, 0.0,
+            )))
+            .with(
+                PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic)
+                    .velocity(Velocity3::linear(10.0, 0.0, 0.0))
+                    .build(),
+            )
+            .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 0.5 }).build())
+            .build();
+        for _ in 0..60 {
+            dispatcher.dispatch(&world);
+        }
+
+        // the ball entered and exited the trigger exactly once
+        let trigger_events = world.read_resource::<TriggerEvents>();
+        let events = trigger_events.read(&mut reader_id).collect::<Vec<_>>();
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|e| e.trigger == trigger && e.entity == ball));
+        assert_eq!(events[0].trigger_type, TriggerType::Enter);
+        assert_eq!(events[1].trigger_type, TriggerType::Exit);
+    }
+}
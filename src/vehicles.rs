@@ -0,0 +1,105 @@
+//! # Vehicles module
+//! Raycast wheels for building arcade vehicles.
+//!
+//! Instead of simulating actual wheel bodies connected by joints, each wheel
+//! of a vehicle is an `Entity` with a `RaycastWheel` `Component` that refers
+//! to the chassis `Entity`. Before every simulation step the
+//! `RaycastWheelSystem` casts a ray along the suspension of each wheel and
+//! pushes the chassis away from the ground with a damped spring. The
+//! resulting `WheelContact` can be used to apply traction and steering
+//! forces to the chassis.
+
+use specs::{Component, DenseVecStorage, Entity};
+
+use crate::nalgebra::{Point3, RealField, Unit, Vector3};
+
+/// The `RaycastWheel` `Component` attaches a wheel to the `RigidBody` of the
+/// `chassis` `Entity`. The suspension starts at `mount_point` and extends
+/// along `direction` for up to `rest_length`, both in the local space of the
+/// chassis. The wheel radius is expected to be included in `rest_length`.
+///
+/// # Example
+///
+/// ```rust
+/// use specs::{Builder, World, WorldExt};
+/// use specs_physics::{
+///     nalgebra::{Point3, Vector3},
+///     vehicles::RaycastWheel,
+/// };
+///
+/// let mut world = World::new();
+/// let chassis = world.create_entity().build();
+///
+/// let wheel = RaycastWheel::<f32>::new(chassis, Point3::new(0.8, -0.25, 1.5), 1.0)
+///     .with_direction(-Vector3::y_axis())
+///     .with_suspension(200.0, 20.0);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastWheel<N: RealField> {
+    pub chassis: Entity,
+    /// The point the suspension is mounted at, in local coordinates of the
+    /// chassis.
+    pub mount_point: Point3<N>,
+    /// The direction the suspension extends into, in local coordinates of the
+    /// chassis; defaults to the negative y axis.
+    pub direction: Unit<Vector3<N>>,
+    /// The length of the fully extended suspension, which exerts no force.
+    pub rest_length: N,
+    /// The force exerted per unit of length the suspension is compressed by.
+    pub stiffness: N,
+    /// The force exerted per unit of velocity the suspension is compressed
+    /// with.
+    pub damping: N,
+    /// The ground touched by the wheel during the last step, if any; updated
+    /// by the `RaycastWheelSystem`.
+    pub contact: Option<WheelContact<N>>,
+}
+
+impl<N: RealField> RaycastWheel<N> {
+    /// Creates a new `RaycastWheel` pointing down without any suspension
+    /// stiffness or damping.
+    pub fn new(chassis: Entity, mount_point: Point3<N>, rest_length: N) -> Self {
+        Self {
+            chassis,
+            mount_point,
+            direction: -Vector3::y_axis(),
+            rest_length,
+            stiffness: N::zero(),
+            damping: N::zero(),
+            contact: None,
+        }
+    }
+
+    /// Sets the `direction` value of the `RaycastWheel`.
+    pub fn with_direction(mut self, direction: Unit<Vector3<N>>) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Sets the `stiffness` and `damping` values of the `RaycastWheel`.
+    pub fn with_suspension(mut self, stiffness: N, damping: N) -> Self {
+        self.stiffness = stiffness;
+        self.damping = damping;
+        self
+    }
+}
+
+impl<N: RealField> Component for RaycastWheel<N> {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The `WheelContact` describes the ground touched by a `RaycastWheel`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WheelContact<N: RealField> {
+    /// The `Entity` of the ground `PhysicsCollider`.
+    pub entity: Entity,
+    /// The point the wheel touches the ground at, in world coordinates.
+    pub point: Point3<N>,
+    /// The normal of the ground surface at the contact point.
+    pub normal: Vector3<N>,
+    /// The length the suspension is compressed by.
+    pub compression: N,
+    /// The magnitude of the suspension force applied to the chassis, e.g. to
+    /// limit the traction of the wheel.
+    pub suspension_force: N,
+}
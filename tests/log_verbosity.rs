@@ -0,0 +1,78 @@
+//! The `PhysicsLogVerbosity` is tested in its own test binary, as capturing the
+//! log records requires installing a global logger.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use specs::{Builder, World, WorldExt};
+use specs_physics::{
+    colliders::Shape,
+    nalgebra::Isometry3,
+    nphysics::object::BodyStatus,
+    parameters::PhysicsLogVerbosity,
+    physics_dispatcher,
+    PhysicsBodyBuilder,
+    PhysicsColliderBuilder,
+    SimplePosition,
+};
+
+/// Counts the records mentioning inserted objects, split by whether they are
+/// logged at the `info` or more severe level or dump the inserted values.
+struct InsertLogCounter;
+
+static INFO_LOGS: AtomicUsize = AtomicUsize::new(0);
+static VALUE_DUMPS: AtomicUsize = AtomicUsize::new(0);
+
+impl Log for InsertLogCounter {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let message = record.args().to_string();
+        if !message.contains("Inserted") {
+            return;
+        }
+
+        if record.level() <= Level::Info {
+            INFO_LOGS.fetch_add(1, Ordering::SeqCst);
+        }
+        if message.contains("with values") {
+            VALUE_DUMPS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn create_ball(world: &mut World) {
+    world
+        .create_entity()
+        .with(SimplePosition::<f32>(Isometry3::identity()))
+        .with(PhysicsBodyBuilder::<f32>::from(BodyStatus::Dynamic).build())
+        .with(PhysicsColliderBuilder::<f32>::from(Shape::Ball { radius: 1.0 }).build())
+        .build();
+}
+
+#[test]
+fn insert_logging() {
+    log::set_logger(&InsertLogCounter).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let mut world = World::new();
+    let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+    dispatcher.setup(&mut world);
+
+    // inserting objects is neither logged at the info level nor dumped by default
+    create_ball(&mut world);
+    dispatcher.dispatch(&world);
+    assert_eq!(INFO_LOGS.load(Ordering::SeqCst), 0);
+    assert_eq!(VALUE_DUMPS.load(Ordering::SeqCst), 0);
+
+    // the verbose PhysicsLogVerbosity dumps the inserted body and collider
+    world.insert(PhysicsLogVerbosity::Verbose);
+    create_ball(&mut world);
+    dispatcher.dispatch(&world);
+    assert_eq!(INFO_LOGS.load(Ordering::SeqCst), 0);
+    assert_eq!(VALUE_DUMPS.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,53 @@
+//! The `TimeStep` warning of the `PhysicsStepperSystem` is tested in its own
+//! test binary, as capturing the log records requires installing a global
+//! logger.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use specs::{World, WorldExt};
+use specs_physics::{parameters::TimeStep, physics_dispatcher, SimplePosition};
+
+/// Counts the warnings about a changed `TimeStep`.
+struct TimeStepWarningCounter;
+
+static TIME_STEP_WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+impl Log for TimeStepWarningCounter {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() == Level::Warn && record.args().to_string().contains("TimeStep") {
+            TIME_STEP_WARNINGS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn warn_once_per_time_step_change() {
+    log::set_logger(&TimeStepWarningCounter).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let mut world = World::new();
+    let mut dispatcher = physics_dispatcher::<f32, SimplePosition<f32>>();
+    dispatcher.setup(&mut world);
+
+    // change the TimeStep once and keep jittering it by less than the epsilon,
+    // like semi-fixed stepping does
+    for i in 0..100 {
+        let jitter = if i % 2 == 0 {
+            0.0
+        } else {
+            std::f32::EPSILON / 4.0
+        };
+        world.insert(TimeStep(1.0 / 30.0 + jitter));
+        dispatcher.dispatch(&world);
+    }
+
+    // only the actual change was warned about
+    assert_eq!(TIME_STEP_WARNINGS.load(Ordering::SeqCst), 1);
+}